@@ -4,9 +4,12 @@ use std::fs::create_dir_all;
 use cosmwasm_schema::{export_schema, remove_schemas, schema_for};
 
 use sei_split_coins::msg::{
-    ExecuteMsg, FeeResponse, InstantiateMsg, OwnerResponse, QueryMsg, WalletResponse,
+    AllAllowancesResponse, EscrowsResponse, ExecuteMsg, FeeExemptionsResponse, FeeResponse,
+    InstantiateMsg, ListWalletsResponse, MigrateMsg, OwnerResponse, PauseStatusResponse, QueryMsg,
+    ReceiveMsg, SolvencyResponse, SplitGroupsResponse, SplitsResponse, VestingResponse,
+    WalletResponse,
 };
-use sei_split_coins::state::Config;
+use sei_split_coins::state::{Config, Escrow, SplitGroup, SplitRecord};
 
 fn main() {
     let mut out_dir = current_dir().unwrap();
@@ -17,8 +20,22 @@ fn main() {
     export_schema(&schema_for!(InstantiateMsg), &out_dir);
     export_schema(&schema_for!(ExecuteMsg), &out_dir);
     export_schema(&schema_for!(QueryMsg), &out_dir);
+    export_schema(&schema_for!(MigrateMsg), &out_dir);
+    export_schema(&schema_for!(ReceiveMsg), &out_dir);
     export_schema(&schema_for!(Config), &out_dir);
     export_schema(&schema_for!(WalletResponse), &out_dir);
+    export_schema(&schema_for!(ListWalletsResponse), &out_dir);
     export_schema(&schema_for!(OwnerResponse), &out_dir);
+    export_schema(&schema_for!(PauseStatusResponse), &out_dir);
+    export_schema(&schema_for!(SolvencyResponse), &out_dir);
     export_schema(&schema_for!(FeeResponse), &out_dir);
+    export_schema(&schema_for!(FeeExemptionsResponse), &out_dir);
+    export_schema(&schema_for!(AllAllowancesResponse), &out_dir);
+    export_schema(&schema_for!(SplitGroup), &out_dir);
+    export_schema(&schema_for!(SplitGroupsResponse), &out_dir);
+    export_schema(&schema_for!(VestingResponse), &out_dir);
+    export_schema(&schema_for!(Escrow), &out_dir);
+    export_schema(&schema_for!(EscrowsResponse), &out_dir);
+    export_schema(&schema_for!(SplitRecord), &out_dir);
+    export_schema(&schema_for!(SplitsResponse), &out_dir);
 }
@@ -1,20 +1,47 @@
 use crate::error::ContractError;
+use crate::fees::{apply_discount, FeeSchedule, BPS_DENOM};
+use crate::migrations::{migrate_from_v1, parse_version, seed_totals};
 use crate::msg::{
-    ExecuteMsg, FeeResponse, InstantiateMsg, OwnerResponse, QueryMsg, WalletResponse,
+    AllAllowancesResponse, AllowanceInfo, DuplicatePolicy, EscrowsResponse, ExecuteMsg,
+    FeeExemption, FeeExemptionsResponse, FeeResponse, InstantiateMsg, ListWalletsResponse,
+    MigrateMsg, OwnerResponse, PauseStatusResponse, QueryMsg, ReceiveMsg, Recipient,
+    RemainderPolicy, SolvencyResponse, SplitGroupsResponse, SplitOptions, SplitsResponse,
+    UpdateConfigMsg, VestingGrantInfo, VestingResponse, WalletResponse,
+};
+use crate::state::{
+    splits, Config, Escrow, EscrowShare, PendingOwner, PendingPayout, Share, SplitGroup,
+    SplitRecord, VestingGrant, ALLOWANCES, CONFIG, CW20_FEES, CW20_REMAINDERS, CW20_WALLETS,
+    ESCROWS, FEES, FEE_EXEMPTIONS, GRANT_SEQ, GROUP_SEQ, PAUSED, PAYOUT_SEQ, PENDING_OWNER,
+    PENDING_PAYOUTS, REMAINDERS, ROUND_ROBIN_CURSOR, SPLITS_BY_RECIPIENT, SPLIT_GROUPS, SPLIT_SEQ,
+    TOTALS, VESTING_GRANTS, VESTING_GRANT_COUNTS, WALLETS,
 };
-use crate::state::{Config, CONFIG, WALLETS};
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coins, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
-    Uint128,
+    attr, coin, coins, from_binary, to_binary, Addr, Attribute, BankMsg, Binary, Coin, Deps,
+    DepsMut, Empty, Env, MessageInfo, Order, Reply, Response, StdResult, Storage, SubMsg,
+    SubMsgResult, Uint128, WasmMsg,
 };
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{AllowanceResponse, Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg, Expiration};
+use cw_storage_plus::{Bound, PrefixBound};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:sei-split-coins";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-const PCT_DENOM: Uint128 = Uint128::new(100);
+// fee cap used when InstantiateMsg.max_fee_bps is not set (20%)
+pub(crate) const DEFAULT_MAX_FEE_BPS: u16 = 2_000;
+// denom withdrawn when WithdrawCoins does not name one
+pub(crate) const DEFAULT_DENOM: &str = "usei";
+// upper bound on recipients per split to keep gas usage predictable
+const MAX_RECIPIENTS: usize = 50;
+// upper bound on accounts paid by one BatchPayout to keep gas usage predictable
+const MAX_BATCH_PAYOUT: usize = 30;
+// upper bound on unreleased vesting grants per recipient, as every withdrawal walks them
+const MAX_VESTING_GRANTS: u32 = 20;
+// page sizes for list queries
+const DEFAULT_LIMIT: u32 = 10;
+const MAX_LIMIT: u32 = 30;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -25,21 +52,49 @@ pub fn instantiate(
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
 
-    // store fee_percent as Uint128 since floats are non-serializable
-    let fee_percent = msg.fee_percent.unwrap_or(Uint128::new(0));
-    if fee_percent > Uint128::new(100) {
+    // fees are stored in basis points since floats are non-serializable; the cap is fixed
+    // here and can only ever be lowered afterwards
+    let max_fee_bps = msg.max_fee_bps.unwrap_or(DEFAULT_MAX_FEE_BPS);
+    if max_fee_bps > BPS_DENOM {
         return Err(ContractError::InvalidParams {});
     }
+    let fee_schedule =
+        resolve_fee_schedule(msg.fee_bps, msg.fee_schedule)?.unwrap_or(FeeSchedule::Percentage {
+            bps: 0,
+            min: None,
+            max: None,
+        });
+    fee_schedule.validate(max_fee_bps)?;
 
     let owner = msg
         .owner
         .and_then(|s| deps.api.addr_validate(s.as_str()).ok())
         .unwrap_or(info.sender);
+    // if fee_collector is None, fees are paid out to the owner
+    let fee_collector = match msg.fee_collector {
+        Some(addr) => deps.api.addr_validate(addr.as_str())?,
+        None => owner.clone(),
+    };
 
     let config = Config {
-        owner: owner.clone(),
+        owner: Some(owner.clone()),
         cw20_addr: deps.api.addr_validate(msg.cw20_addr.as_str())?,
-        fee_percent: fee_percent,
+        allowed_cw20s: msg
+            .allowed_cw20s
+            .unwrap_or_default()
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<_>>()?,
+        fee_collector: fee_collector.clone(),
+        fee_schedule: fee_schedule.clone(),
+        max_fee_bps,
+        allowed_denoms: validate_allowed_denoms(msg.allowed_denoms)?,
+        push_payouts: msg.push_payouts.unwrap_or(false),
+        operators: vec![],
+        guardian: None,
+        remainder_policy: msg.remainder_policy.unwrap_or_default(),
+        min_deposits: validate_min_deposits(msg.min_deposits.unwrap_or_default())?,
+        recipient_policy: msg.recipient_policy.unwrap_or_default(),
     };
 
     CONFIG.save(deps.storage, &config)?;
@@ -48,7 +103,10 @@ pub fn instantiate(
         .add_attribute("method", "instantiate")
         .add_attribute("owner", owner)
         .add_attribute("cw20_addr", msg.cw20_addr)
-        .add_attribute("fee_percent", fee_percent.to_string()))
+        .add_attribute("fee_collector", fee_collector)
+        .add_attribute("fee_schedule", fee_schedule.to_string())
+        .add_attribute("max_fee_bps", max_fee_bps.to_string())
+        .add_attribute("push_payouts", config.push_payouts.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -58,145 +116,2402 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    assert_not_paused(deps.storage, &msg)?;
     match msg {
         ExecuteMsg::SplitCoins {
             target_addr1,
             target_addr2,
-        } => execute_split_coins(deps, _env, info, target_addr1, target_addr2),
-        ExecuteMsg::WithdrawCoins { amount } => execute_withdraw_coins(deps, _env, info, amount),
+            options,
+        } => execute_split_coins(deps, _env, info, target_addr1, target_addr2, options),
+        ExecuteMsg::WeightedSplitCoins {
+            recipients,
+            options,
+        } => execute_weighted_split_coins(deps, _env, info, recipients, options),
+        ExecuteMsg::WithdrawCoins {
+            amount,
+            denom,
+            recipient,
+        } => execute_withdraw_coins(deps, _env, info, amount, denom, recipient),
+        ExecuteMsg::Receive(msg) => execute_receive(deps, _env, info, msg),
+        ExecuteMsg::WithdrawCw20 {
+            token,
+            amount,
+            recipient,
+        } => execute_withdraw_cw20(deps, _env, info, token, amount, recipient),
+        ExecuteMsg::WithdrawFees { recipient, token } => {
+            execute_withdraw_fees(deps, _env, info, recipient, token)
+        }
+        ExecuteMsg::SweepSurplus { recipient, denom } => {
+            execute_sweep_surplus(deps, _env, info, recipient, denom)
+        }
+        ExecuteMsg::UpdateConfig(msg) => execute_update_config(deps, _env, info, msg),
+        ExecuteMsg::ProposeNewOwner { owner, expiry } => {
+            execute_propose_new_owner(deps, _env, info, owner, expiry)
+        }
+        ExecuteMsg::CancelOwnershipTransfer {} => {
+            execute_cancel_ownership_transfer(deps, _env, info)
+        }
+        ExecuteMsg::AcceptOwnership {} => execute_accept_ownership(deps, _env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, _env, info),
+        ExecuteMsg::SetGuardian { guardian } => execute_set_guardian(deps, _env, info, guardian),
+        ExecuteMsg::Pause {
+            splits,
+            withdrawals,
+        } => execute_pause(deps, _env, info, splits, withdrawals),
+        ExecuteMsg::Unpause {
+            splits,
+            withdrawals,
+        } => execute_unpause(deps, _env, info, splits, withdrawals),
+        ExecuteMsg::AddFeeExemption { addr, discount_bps } => {
+            execute_add_fee_exemption(deps, _env, info, addr, discount_bps)
+        }
+        ExecuteMsg::RemoveFeeExemption { addr } => {
+            execute_remove_fee_exemption(deps, _env, info, addr)
+        }
+        ExecuteMsg::IncreaseAllowance {
+            spender,
+            amount,
+            denom,
+            expires,
+        } => execute_increase_allowance(deps, _env, info, spender, amount, denom, expires),
+        ExecuteMsg::DecreaseAllowance {
+            spender,
+            amount,
+            denom,
+            expires,
+        } => execute_decrease_allowance(deps, _env, info, spender, amount, denom, expires),
+        ExecuteMsg::WithdrawFrom {
+            owner,
+            amount,
+            denom,
+            recipient,
+        } => execute_withdraw_from(deps, _env, info, owner, amount, denom, recipient),
+        ExecuteMsg::CreateSplitGroup {
+            name,
+            recipients,
+            mutable,
+        } => execute_create_split_group(deps, _env, info, name, recipients, mutable),
+        ExecuteMsg::UpdateSplitGroup {
+            group_id,
+            name,
+            recipients,
+        } => execute_update_split_group(deps, _env, info, group_id, name, recipients),
+        ExecuteMsg::DeleteSplitGroup { group_id } => {
+            execute_delete_split_group(deps, _env, info, group_id)
+        }
+        ExecuteMsg::SplitToGroup { group_id, options } => {
+            execute_split_to_group(deps, _env, info, group_id, options)
+        }
+        ExecuteMsg::Claim { split_id } => execute_claim(deps, _env, info, split_id),
+        ExecuteMsg::Reclaim { split_id } => execute_reclaim(deps, _env, info, split_id),
+        ExecuteMsg::BatchPayout {
+            addrs,
+            start_after,
+            limit,
+        } => execute_batch_payout(deps, _env, info, addrs, start_after, limit),
+        ExecuteMsg::Transfer {
+            recipient,
+            amount,
+            denom,
+        } => execute_transfer(deps, _env, info, recipient, amount, denom),
+        ExecuteMsg::TransferFrom {
+            owner,
+            recipient,
+            amount,
+            denom,
+        } => execute_transfer_from(deps, _env, info, owner, recipient, amount, denom),
     }
 }
 
-pub fn execute_split_coins(
+pub fn execute_add_fee_exemption(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    target_addr1: String,
-    target_addr2: String,
+    addr: String,
+    discount_bps: Option<u16>,
 ) -> Result<Response, ContractError> {
-    // only execute split coins if usei is the only token sent to execute
-    if info.funds.len() != 1 || info.funds[0].denom != "usei" {
-        return Err(ContractError::InvalidTokenTransfer {});
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    // if discount_bps is None, the address is exempt from fees entirely
+    let discount_bps = discount_bps.unwrap_or(BPS_DENOM);
+    if discount_bps == 0 || discount_bps > BPS_DENOM {
+        return Err(ContractError::InvalidParams {});
+    }
+    let addr = deps.api.addr_validate(&addr)?;
+    FEE_EXEMPTIONS.save(deps.storage, addr.clone(), &discount_bps)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "AddFeeExemption")
+        .add_attribute("addr", addr)
+        .add_attribute("discount_bps", discount_bps.to_string()))
+}
+
+pub fn execute_remove_fee_exemption(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    addr: String,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let addr = deps.api.addr_validate(&addr)?;
+    FEE_EXEMPTIONS.remove(deps.storage, addr.clone());
+
+    Ok(Response::new()
+        .add_attribute("action", "RemoveFeeExemption")
+        .add_attribute("addr", addr))
+}
+
+pub fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: UpdateConfigMsg,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    // only the fields that are set are updated
+    if let Some(max_fee_bps) = msg.max_fee_bps {
+        // the cap protects senders, so the owner may only ever lower it
+        if max_fee_bps > config.max_fee_bps {
+            return Err(ContractError::InvalidParams {});
+        }
+        config.max_fee_bps = max_fee_bps;
+    }
+    if let Some(fee_schedule) = resolve_fee_schedule(msg.fee_bps, msg.fee_schedule)? {
+        config.fee_schedule = fee_schedule;
+    }
+    config.fee_schedule.validate(config.max_fee_bps)?;
+    if let Some(fee_collector) = msg.fee_collector {
+        config.fee_collector = deps.api.addr_validate(&fee_collector)?;
+    }
+    if msg.allowed_denoms.is_some() {
+        config.allowed_denoms = validate_allowed_denoms(msg.allowed_denoms)?;
     }
+    if let Some(push_payouts) = msg.push_payouts {
+        config.push_payouts = push_payouts;
+    }
+    if let Some(remainder_policy) = msg.remainder_policy {
+        config.remainder_policy = remainder_policy;
+    }
+    if let Some(min_deposits) = msg.min_deposits {
+        config.min_deposits = validate_min_deposits(min_deposits)?;
+    }
+    if let Some(recipient_policy) = msg.recipient_policy {
+        config.recipient_policy = recipient_policy;
+    }
+    if let Some(operators) = msg.operators {
+        config.operators = operators
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<_>>()?;
+    }
+    if let Some(allowed_cw20s) = msg.allowed_cw20s {
+        config.allowed_cw20s = allowed_cw20s
+            .iter()
+            .map(|addr| deps.api.addr_validate(addr))
+            .collect::<StdResult<_>>()?;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "UpdateConfig")
+        .add_attribute("fee_collector", config.fee_collector)
+        .add_attribute("fee_schedule", config.fee_schedule.to_string())
+        .add_attribute("max_fee_bps", config.max_fee_bps.to_string())
+        .add_attribute(
+            "allowed_denoms",
+            config
+                .allowed_denoms
+                .map(|denoms| denoms.join(","))
+                .unwrap_or_else(|| String::from("*")),
+        )
+        .add_attribute("push_payouts", config.push_payouts.to_string())
+        .add_attribute(
+            "operators",
+            config
+                .operators
+                .iter()
+                .map(|addr| addr.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+        .add_attribute(
+            "allowed_cw20s",
+            config
+                .allowed_cw20s
+                .iter()
+                .map(|addr| addr.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+        .add_attribute("remainder_policy", config.remainder_policy.to_string())
+        .add_attribute(
+            "min_deposits",
+            config
+                .min_deposits
+                .iter()
+                .map(|coin| coin.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        )
+        .add_attribute("recipient_policy", config.recipient_policy.to_string()))
+}
+
+pub fn execute_propose_new_owner(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    expiry: Option<Expiration>,
+) -> Result<Response, ContractError> {
     let config: Config = CONFIG.load(deps.storage)?;
-    let amount = info.funds[0].amount;
-    let fees_collected = amount
-        .checked_mul(config.fee_percent)
-        .or_else(|_| Err(ContractError::InvalidParams {}))?
-        .checked_div(PCT_DENOM)
-        .or_else(|_| Err(ContractError::InvalidParams {}))?;
-    let amount = amount - fees_collected;
-    let half_amount = amount / Uint128::new(2);
-
-    let target_addr1 = deps.api.addr_validate(&target_addr1)?;
-    let target_addr2 = deps.api.addr_validate(&target_addr2)?;
-
-    // give target_addr1 extra token if info.funds[0].amount is odd (cannot split evenly)
-    WALLETS.update(
+    assert_owner(&config, &info.sender)?;
+
+    // a proposal that can never be accepted is almost certainly a mistake
+    if expiry.map_or(false, |e| e.is_expired(&env.block)) {
+        return Err(ContractError::InvalidParams {});
+    }
+    let new_owner = deps.api.addr_validate(&owner)?;
+    PENDING_OWNER.save(
         deps.storage,
-        target_addr1.clone(),
-        |balance| -> StdResult<_> {
-            Ok(balance
-                .unwrap_or_default()
-                .checked_add(half_amount + (amount % Uint128::new(2)))?)
+        &PendingOwner {
+            new_owner: new_owner.clone(),
+            expiry,
         },
     )?;
-    WALLETS.update(
-        deps.storage,
-        target_addr2.clone(),
-        |balance| -> StdResult<_> { Ok(balance.unwrap_or_default().checked_add(half_amount)?) },
-    )?;
-    WALLETS.update(
-        deps.storage,
-        config.cw20_addr.clone(),
-        |balance| -> StdResult<_> { Ok(balance.unwrap_or_default().checked_add(fees_collected)?) },
-    )?;
 
-    let res = Response::new()
-        .add_attribute("action", "SplitCoins")
-        .add_attribute("from", info.sender)
-        .add_attribute("amount", amount)
-        .add_attribute("target_addr1", target_addr1)
+    Ok(Response::new()
+        .add_attribute("action", "ProposeNewOwner")
+        .add_attribute("new_owner", new_owner))
+}
+
+pub fn execute_cancel_ownership_transfer(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    if PENDING_OWNER.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::NoPendingOwner {});
+    }
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new().add_attribute("action", "CancelOwnershipTransfer"))
+}
+
+pub fn execute_accept_ownership(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending = PENDING_OWNER
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingOwner {})?;
+    if info.sender != pending.new_owner {
+        return Err(ContractError::Unauthorized {});
+    }
+    if pending.expiry.map_or(false, |e| e.is_expired(&env.block)) {
+        return Err(ContractError::OwnershipProposalExpired {});
+    }
+
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    let previous_owner = config.owner.replace(pending.new_owner.clone());
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "AcceptOwnership")
         .add_attribute(
-            "target_addr1_amount",
-            half_amount + (amount % Uint128::new(2)),
+            "previous_owner",
+            previous_owner.map(String::from).unwrap_or_default(),
         )
-        .add_attribute("target_addr2", target_addr2)
-        .add_attribute("target_addr2_amount", half_amount)
-        .add_attribute("fees_collected", fees_collected);
+        .add_attribute("owner", pending.new_owner))
+}
 
-    Ok(res)
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    // irreversible - every owner-gated message is rejected from now on
+    config.owner = None;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_OWNER.remove(deps.storage);
+
+    Ok(Response::new()
+        .add_attribute("action", "RenounceOwnership")
+        .add_attribute("previous_owner", info.sender))
 }
 
-pub fn execute_withdraw_coins(
+pub fn execute_set_guardian(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    amount: Option<Uint128>,
+    guardian: Option<String>,
 ) -> Result<Response, ContractError> {
-    // if amount is None, assume the caller wants to withdraw all coins at info.sender
-    if amount.is_some() && amount.unwrap() == Uint128::new(0) {
-        return Err(ContractError::InvalidParams {});
+    let mut config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    config.guardian = guardian
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "SetGuardian")
+        .add_attribute(
+            "guardian",
+            config
+                .guardian
+                .map(String::from)
+                .unwrap_or_else(|| String::from("none")),
+        ))
+}
+
+pub fn execute_pause(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    splits: bool,
+    withdrawals: bool,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    if config.guardian.as_ref() != Some(&info.sender) {
+        assert_owner(&config, &info.sender)?;
     }
 
-    // set withdraw_amount if balance exists - must be mutable since value is found at run-time
-    let mut withdraw_amount: Uint128 = Uint128::new(0);
-    WALLETS.update(
-        deps.storage,
-        info.sender.clone(),
-        |balance| -> Result<Uint128, ContractError> {
-            match balance {
-                Some(_) => {
-                    withdraw_amount = amount.unwrap_or_else(|| balance.unwrap());
-                    balance
-                        .unwrap()
-                        .checked_sub(withdraw_amount)
-                        .or_else(|_| Err(ContractError::InsufficientFunds {}))
-                }
-                None => Err(ContractError::InsufficientFunds {}),
+    let mut paused = PAUSED.may_load(deps.storage)?.unwrap_or_default();
+    paused.splits |= splits;
+    paused.withdrawals |= withdrawals;
+    PAUSED.save(deps.storage, &paused)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "Pause")
+        .add_attribute("splits_paused", paused.splits.to_string())
+        .add_attribute("withdrawals_paused", paused.withdrawals.to_string()))
+}
+
+pub fn execute_unpause(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    splits: bool,
+    withdrawals: bool,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+
+    let mut paused = PAUSED.may_load(deps.storage)?.unwrap_or_default();
+    paused.splits &= !splits;
+    paused.withdrawals &= !withdrawals;
+    PAUSED.save(deps.storage, &paused)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "Unpause")
+        .add_attribute("splits_paused", paused.splits.to_string())
+        .add_attribute("withdrawals_paused", paused.withdrawals.to_string()))
+}
+
+/// Rejects deposits while splits are paused and anything paying out while withdrawals are.
+/// Internal moves such as `Transfer` and `Claim` keep working either way.
+fn assert_not_paused(storage: &dyn Storage, msg: &ExecuteMsg) -> Result<(), ContractError> {
+    let paused = PAUSED.may_load(storage)?.unwrap_or_default();
+    let blocked = match msg {
+        ExecuteMsg::SplitCoins { .. }
+        | ExecuteMsg::WeightedSplitCoins { .. }
+        | ExecuteMsg::SplitToGroup { .. }
+        | ExecuteMsg::Receive(_) => paused.splits,
+        ExecuteMsg::WithdrawCoins { .. }
+        | ExecuteMsg::WithdrawCw20 { .. }
+        | ExecuteMsg::WithdrawFees { .. }
+        | ExecuteMsg::SweepSurplus { .. }
+        | ExecuteMsg::WithdrawFrom { .. }
+        | ExecuteMsg::BatchPayout { .. }
+        | ExecuteMsg::Reclaim { .. } => paused.withdrawals,
+        _ => false,
+    };
+    if blocked {
+        return Err(ContractError::Paused {});
+    }
+    Ok(())
+}
+
+/// `fee_bps` is shorthand for a percentage schedule without clamps, so setting both is ambiguous
+fn resolve_fee_schedule(
+    fee_bps: Option<u16>,
+    fee_schedule: Option<FeeSchedule>,
+) -> Result<Option<FeeSchedule>, ContractError> {
+    match (fee_bps, fee_schedule) {
+        (Some(_), Some(_)) => Err(ContractError::InvalidParams {}),
+        (Some(bps), None) => Ok(Some(FeeSchedule::Percentage {
+            bps,
+            min: None,
+            max: None,
+        })),
+        (None, fee_schedule) => Ok(fee_schedule),
+    }
+}
+
+/// Coins sent or credited to the contract itself would end up as untracked surplus
+fn assert_not_contract(env: &Env, recipient: &Addr) -> Result<(), ContractError> {
+    if *recipient == env.contract.address {
+        return Err(ContractError::ContractRecipient {});
+    }
+    Ok(())
+}
+
+fn assert_owner(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    match &config.owner {
+        Some(owner) if owner == sender => Ok(()),
+        _ => Err(ContractError::Unauthorized {}),
+    }
+}
+
+fn assert_operator(config: &Config, sender: &Addr) -> Result<(), ContractError> {
+    if config.operators.contains(sender) {
+        return Ok(());
+    }
+    assert_owner(config, sender)
+}
+
+/// An empty list is treated the same as no list - every native denom is accepted
+fn validate_allowed_denoms(
+    allowed_denoms: Option<Vec<String>>,
+) -> Result<Option<Vec<String>>, ContractError> {
+    match allowed_denoms {
+        Some(denoms) if !denoms.is_empty() => {
+            if denoms.iter().any(|d| d.trim().is_empty()) {
+                return Err(ContractError::InvalidParams {});
             }
-        },
+            Ok(Some(denoms))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// At most one non-zero minimum per denom
+fn validate_min_deposits(min_deposits: Vec<Coin>) -> Result<Vec<Coin>, ContractError> {
+    for (i, min) in min_deposits.iter().enumerate() {
+        if min.amount.is_zero()
+            || min.denom.trim().is_empty()
+            || min_deposits[..i].iter().any(|m| m.denom == min.denom)
+        {
+            return Err(ContractError::InvalidParams {});
+        }
+    }
+    Ok(min_deposits)
+}
+
+pub fn execute_split_coins(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    target_addr1: String,
+    target_addr2: String,
+    options: Option<SplitOptions>,
+) -> Result<Response, ContractError> {
+    let recipients = even_split(target_addr1, target_addr2);
+    let options = options.unwrap_or_default();
+    let split = split_funds(
+        deps.branch(),
+        &env,
+        &info.sender,
+        &info.funds,
+        recipients,
+        Ledger::Native,
+        options.remainder,
+    )?;
+    let (messages, attributes) = settle_shares(deps.storage, &env, &split, options)?;
+
+    Ok(split_coins_response(info.sender, &split)
+        .add_submessages(messages)
+        .add_attributes(attributes))
+}
+
+pub fn execute_weighted_split_coins(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipients: Vec<Recipient>,
+    options: Option<SplitOptions>,
+) -> Result<Response, ContractError> {
+    let options = options.unwrap_or_default();
+    let split = split_funds(
+        deps.branch(),
+        &env,
+        &info.sender,
+        &info.funds,
+        recipients,
+        Ledger::Native,
+        options.remainder,
+    )?;
+    let (messages, attributes) = settle_shares(deps.storage, &env, &split, options)?;
+
+    Ok(
+        weighted_split_response("WeightedSplitCoins", info.sender, &split)
+            .add_submessages(messages)
+            .add_attributes(attributes),
+    )
+}
+
+pub fn execute_split_to_group(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    options: Option<SplitOptions>,
+) -> Result<Response, ContractError> {
+    let group = SPLIT_GROUPS.load(deps.storage, group_id)?;
+    let options = options.unwrap_or_default();
+    let split = split_funds(
+        deps.branch(),
+        &env,
+        &info.sender,
+        &info.funds,
+        group.recipients,
+        Ledger::Native,
+        options.remainder,
     )?;
+    let (messages, attributes) = settle_shares(deps.storage, &env, &split, options)?;
+
+    Ok(weighted_split_response("SplitToGroup", info.sender, &split)
+        .add_attribute("group_id", group_id.to_string())
+        .add_submessages(messages)
+        .add_attributes(attributes))
+}
+
+pub fn execute_claim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    split_id: u64,
+) -> Result<Response, ContractError> {
+    let mut escrow = ESCROWS
+        .may_load(deps.storage, split_id)?
+        .ok_or(ContractError::NothingToClaim {})?;
+    if escrow.expires.is_expired(&env.block) {
+        return Err(ContractError::ClaimExpired {});
+    }
+    // a recipient listed more than once claims all of their shares together
+    let mut claimed = vec![];
+    for share in escrow.shares.iter_mut() {
+        if share.recipient == info.sender && !share.claimed {
+            share.claimed = true;
+            claimed.extend(share.amount.iter().cloned());
+        }
+    }
+    if claimed.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    for c in claimed.iter() {
+        credit_wallet(
+            deps.storage,
+            Ledger::Native,
+            &info.sender,
+            &c.denom,
+            c.amount,
+        )?;
+    }
+    if escrow.shares.iter().all(|share| share.claimed) {
+        ESCROWS.remove(deps.storage, split_id);
+    } else {
+        ESCROWS.save(deps.storage, split_id, &escrow)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "Claim")
+        .add_attribute("split_id", split_id.to_string())
+        .add_attribute("recipient", info.sender)
+        .add_attribute("amount", coins_to_string(&claimed)))
+}
+
+pub fn execute_reclaim(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    split_id: u64,
+) -> Result<Response, ContractError> {
+    let escrow = ESCROWS
+        .may_load(deps.storage, split_id)?
+        .ok_or(ContractError::NothingToClaim {})?;
+    if escrow.sender != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !escrow.expires.is_expired(&env.block) {
+        return Err(ContractError::ClaimNotExpired {});
+    }
+    // merge the unclaimed shares into one coin per denom
+    let mut unclaimed: Vec<Coin> = vec![];
+    for share in escrow.shares.iter().filter(|s| !s.claimed) {
+        for c in share.amount.iter() {
+            match unclaimed.iter_mut().find(|u| u.denom == c.denom) {
+                Some(u) => u.amount += c.amount,
+                None => unclaimed.push(c.clone()),
+            }
+        }
+    }
+    if unclaimed.is_empty() {
+        return Err(ContractError::NothingToClaim {});
+    }
+    for c in unclaimed.iter() {
+        assert_solvent(deps.as_ref(), &env, &c.denom)?;
+    }
+    ESCROWS.remove(deps.storage, split_id);
+    record_payout(deps.storage, &unclaimed)?;
 
     Ok(Response::new()
         .add_message(BankMsg::Send {
             to_address: info.sender.to_string(),
-            amount: coins(withdraw_amount.u128(), "usei"),
+            amount: unclaimed.clone(),
         })
-        .add_attribute("action", "WithdrawCoins")
-        .add_attribute("addr", info.sender)
-        .add_attribute("amount", withdraw_amount))
+        .add_attribute("action", "Reclaim")
+        .add_attribute("split_id", split_id.to_string())
+        .add_attribute("sender", info.sender)
+        .add_attribute("amount", coins_to_string(&unclaimed)))
 }
 
-#[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
-    match msg {
-        QueryMsg::GetOwner {} => to_binary(&query_owner(deps)?),
-        QueryMsg::GetWallet { addr } => to_binary(&query_wallet(deps, addr)?),
-        QueryMsg::GetFee {} => to_binary(&query_fee(deps)?),
+pub fn execute_create_split_group(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    name: String,
+    recipients: Vec<Recipient>,
+    mutable: bool,
+) -> Result<Response, ContractError> {
+    let recipients = validate_group_recipients(deps.as_ref(), recipients)?;
+    let id = GROUP_SEQ.may_load(deps.storage)?.unwrap_or_default() + 1;
+    GROUP_SEQ.save(deps.storage, &id)?;
+    let group = SplitGroup {
+        id,
+        name,
+        creator: info.sender,
+        recipients,
+        mutable,
+    };
+    SPLIT_GROUPS.save(deps.storage, id, &group)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "CreateSplitGroup")
+        .add_attribute("group_id", id.to_string())
+        .add_attribute("name", group.name)
+        .add_attribute("creator", group.creator)
+        .add_attribute("mutable", mutable.to_string()))
+}
+
+pub fn execute_update_split_group(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    group_id: u64,
+    name: Option<String>,
+    recipients: Option<Vec<Recipient>>,
+) -> Result<Response, ContractError> {
+    let mut group = SPLIT_GROUPS.load(deps.storage, group_id)?;
+    if group.creator != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    if !group.mutable {
+        return Err(ContractError::ImmutableGroup { group_id });
+    }
+    if let Some(name) = name {
+        group.name = name;
+    }
+    if let Some(recipients) = recipients {
+        group.recipients = validate_group_recipients(deps.as_ref(), recipients)?;
     }
+    SPLIT_GROUPS.save(deps.storage, group_id, &group)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "UpdateSplitGroup")
+        .add_attribute("group_id", group_id.to_string())
+        .add_attribute("name", group.name))
 }
 
-fn query_owner(deps: Deps) -> StdResult<OwnerResponse> {
-    let config: Config = CONFIG.load(deps.storage)?;
-    Ok(OwnerResponse {
-        owner: config.owner,
-    })
+pub fn execute_delete_split_group(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    group_id: u64,
+) -> Result<Response, ContractError> {
+    let group = SPLIT_GROUPS.load(deps.storage, group_id)?;
+    if group.creator != info.sender {
+        return Err(ContractError::Unauthorized {});
+    }
+    SPLIT_GROUPS.remove(deps.storage, group_id);
+
+    Ok(Response::new()
+        .add_attribute("action", "DeleteSplitGroup")
+        .add_attribute("group_id", group_id.to_string()))
 }
 
-fn query_wallet(deps: Deps, addr: Addr) -> StdResult<WalletResponse> {
-    let amount = WALLETS.load(deps.storage, addr.clone()).unwrap_or_default();
-    Ok(WalletResponse {
-        addr: addr,
-        amount: amount,
-    })
+/// Checks the shape of a group's recipients up front. The contract address and the recipient
+/// policy are only checked when the group is split to, as the policy may change in between.
+fn validate_group_recipients(
+    deps: Deps,
+    recipients: Vec<Recipient>,
+) -> Result<Vec<Recipient>, ContractError> {
+    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        return Err(ContractError::InvalidParams {});
+    }
+    recipients
+        .into_iter()
+        .map(|r| {
+            if r.weight == 0 {
+                return Err(ContractError::InvalidParams {});
+            }
+            Ok(Recipient {
+                addr: deps.api.addr_validate(&r.addr)?.to_string(),
+                weight: r.weight,
+            })
+        })
+        .collect()
 }
 
-fn query_fee(deps: Deps) -> StdResult<FeeResponse> {
+pub fn execute_receive(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wrapper: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // info.sender is the CW20 token contract, wrapper.sender is the account that sent the tokens.
+    // Anyone can call Receive, so only listed tokens are trusted to have really sent something.
     let config: Config = CONFIG.load(deps.storage)?;
+    if info.sender != config.cw20_addr && !config.allowed_cw20s.contains(&info.sender) {
+        return Err(ContractError::TokenNotAllowed {
+            token: info.sender.to_string(),
+        });
+    }
+    let sender = deps.api.addr_validate(&wrapper.sender)?;
+    let funds = coins(wrapper.amount.u128(), info.sender.as_str());
 
-    Ok(FeeResponse {
-        addr: config.cw20_addr,
-        fee_percent: config.fee_percent,
-    })
+    let res = match from_binary(&wrapper.msg)? {
+        ReceiveMsg::SplitCoins {
+            target_addr1,
+            target_addr2,
+        } => {
+            let recipients = even_split(target_addr1, target_addr2);
+            let split = split_funds(
+                deps.branch(),
+                &env,
+                &sender,
+                &funds,
+                recipients,
+                Ledger::Cw20,
+                None,
+            )?;
+            let (messages, attributes) =
+                settle_shares(deps.storage, &env, &split, SplitOptions::default())?;
+            split_coins_response(sender, &split)
+                .add_submessages(messages)
+                .add_attributes(attributes)
+        }
+        ReceiveMsg::WeightedSplitCoins { recipients } => {
+            let split = split_funds(
+                deps.branch(),
+                &env,
+                &sender,
+                &funds,
+                recipients,
+                Ledger::Cw20,
+                None,
+            )?;
+            let (messages, attributes) =
+                settle_shares(deps.storage, &env, &split, SplitOptions::default())?;
+            weighted_split_response("WeightedSplitCoins", sender, &split)
+                .add_submessages(messages)
+                .add_attributes(attributes)
+        }
+        ReceiveMsg::SplitToGroup { group_id } => {
+            let group = SPLIT_GROUPS.load(deps.storage, group_id)?;
+            let split = split_funds(
+                deps.branch(),
+                &env,
+                &sender,
+                &funds,
+                group.recipients,
+                Ledger::Cw20,
+                None,
+            )?;
+            let (messages, attributes) =
+                settle_shares(deps.storage, &env, &split, SplitOptions::default())?;
+            weighted_split_response("SplitToGroup", sender, &split)
+                .add_attribute("group_id", group_id.to_string())
+                .add_submessages(messages)
+                .add_attributes(attributes)
+        }
+    };
+
+    Ok(res.add_attribute("token", info.sender))
+}
+
+/// An even two-way split; by default the odd token (if any) goes to target_addr1
+fn even_split(target_addr1: String, target_addr2: String) -> Vec<Recipient> {
+    vec![
+        Recipient {
+            addr: target_addr1,
+            weight: 1,
+        },
+        Recipient {
+            addr: target_addr2,
+            weight: 1,
+        },
+    ]
+}
+
+fn split_coins_response(sender: Addr, split: &SplitResult) -> Response {
+    let (addr1, share1) = &split.shares[0];
+    // merging a duplicate target leaves a single share; the attributes keep their layout and
+    // report the merged target again with nothing left for it
+    let (addr2, share2) = match split.shares.get(1) {
+        Some((addr, share)) => (addr.clone(), share.clone()),
+        None => (
+            addr1.clone(),
+            share1.iter().map(|c| coin(0, &c.denom)).collect(),
+        ),
+    };
+    Response::new()
+        .add_attribute("action", "SplitCoins")
+        .add_attribute("from", sender)
+        .add_attribute("amount", split.format(&split.amount))
+        .add_attribute("target_addr1", addr1.clone())
+        .add_attribute("target_addr1_amount", split.format(share1))
+        .add_attribute("target_addr2", addr2)
+        .add_attribute("target_addr2_amount", split.format(&share2))
+        .add_attribute("fees_collected", split.format(&split.fees_collected))
+}
+
+fn weighted_split_response(action: &str, sender: Addr, split: &SplitResult) -> Response {
+    let mut res = Response::new()
+        .add_attribute("action", action)
+        .add_attribute("from", sender)
+        .add_attribute("amount", split.format(&split.amount))
+        .add_attribute("fees_collected", split.format(&split.fees_collected));
+    for (addr, share) in split.shares.iter() {
+        res = res
+            .add_attribute("recipient", addr)
+            .add_attribute("recipient_amount", split.format(share));
+    }
+    res
+}
+
+/// Balance ledger a split is credited to: native coins are tracked in `WALLETS` by denom and
+/// CW20 tokens in `CW20_WALLETS` by token contract address
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Ledger {
+    Native,
+    Cw20,
+}
+
+/// Outcome of a split: the coins split after fees, the fees collected and each recipient's share.
+/// For CW20 splits the coin denom holds the token contract address.
+struct SplitResult {
+    ledger: Ledger,
+    sender: Addr,
+    amount: Vec<Coin>,
+    fees_collected: Vec<Coin>,
+    shares: Vec<(Addr, Vec<Coin>)>,
+}
+
+impl SplitResult {
+    /// Formats coins for response attributes - "50usei,10uatom" for native splits and the bare
+    /// amount for CW20 splits, since the token is reported in its own attribute
+    fn format(&self, coins: &[Coin]) -> String {
+        match self.ledger {
+            Ledger::Native => coins_to_string(coins),
+            Ledger::Cw20 => coins
+                .iter()
+                .map(|c| c.amount.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+}
+
+/// Takes the fee out of every coin in `funds` and splits the rest of each coin across
+/// `recipients` by weight. Fees are accrued here; the shares are left to `settle_shares`.
+fn split_funds(
+    deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    funds: &[Coin],
+    recipients: Vec<Recipient>,
+    ledger: Ledger,
+    remainder: Option<RemainderPolicy>,
+) -> Result<SplitResult, ContractError> {
+    // every coin sent with the message is split independently, but something must be sent
+    if funds.is_empty() || funds.iter().any(|c| c.amount.is_zero()) {
+        return Err(ContractError::InvalidTokenTransfer {});
+    }
+    if recipients.is_empty() || recipients.len() > MAX_RECIPIENTS {
+        return Err(ContractError::InvalidParams {});
+    }
+    let config: Config = CONFIG.load(deps.storage)?;
+    if let (Ledger::Native, Some(allowed_denoms)) = (ledger, &config.allowed_denoms) {
+        if let Some(fund) = funds.iter().find(|c| !allowed_denoms.contains(&c.denom)) {
+            return Err(ContractError::DenomNotAllowed {
+                denom: fund.denom.clone(),
+            });
+        }
+    }
+    for fund in funds.iter() {
+        if let Some(min) = config.min_deposits.iter().find(|m| m.denom == fund.denom) {
+            if fund.amount < min.amount {
+                return Err(ContractError::DepositTooSmall {
+                    denom: min.denom.clone(),
+                    min: min.amount,
+                });
+            }
+        }
+    }
+    let recipients = check_recipients(deps.as_ref(), env, &config, sender, recipients)?;
+    let weights: Vec<u64> = recipients.iter().map(|(_, weight)| *weight).collect();
+    let mut result = SplitResult {
+        ledger,
+        sender: sender.clone(),
+        amount: vec![],
+        fees_collected: vec![],
+        shares: recipients
+            .into_iter()
+            .map(|(addr, _)| (addr, vec![]))
+            .collect(),
+    };
+
+    let policy = remainder.unwrap_or(config.remainder_policy);
+
+    for fund in funds.iter() {
+        let mut fees_collected = fee_for_sender(deps.as_ref(), &config, sender, fund)?;
+        let mut amount = fund.amount - fees_collected;
+        if policy == RemainderPolicy::Bucket {
+            amount += load_bucket(deps.storage, ledger, &fund.denom)?;
+        }
+
+        let (mut shares, remainder) = weighted_shares(amount, &weights)?;
+        match policy {
+            RemainderPolicy::First => spread_remainder(&mut shares, 0, remainder),
+            RemainderPolicy::LargestWeight => {
+                // max_by_key keeps the last maximum, so search in reverse for the first
+                let largest = weights
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .max_by_key(|(_, w)| **w)
+                    .map(|(i, _)| i)
+                    .unwrap_or_default();
+                shares[largest] += remainder;
+            }
+            RemainderPolicy::RoundRobin => {
+                let cursor = ROUND_ROBIN_CURSOR
+                    .may_load(deps.storage)?
+                    .unwrap_or_default();
+                let start = (cursor % shares.len() as u64) as usize;
+                spread_remainder(&mut shares, start, remainder);
+                ROUND_ROBIN_CURSOR.save(deps.storage, &(cursor + remainder.u128() as u64))?;
+            }
+            RemainderPolicy::FeeCollector => fees_collected += remainder,
+            RemainderPolicy::Bucket => save_bucket(deps.storage, ledger, &fund.denom, remainder)?,
+        }
+        if let Some(i) = shares.iter().position(|s| s.is_zero()) {
+            return Err(ContractError::ZeroShare {
+                recipient: result.shares[i].0.to_string(),
+            });
+        }
+        let amount: Uint128 = shares.iter().sum();
+
+        for ((_, received), share) in result.shares.iter_mut().zip(shares) {
+            received.push(coin(share.u128(), &fund.denom));
+        }
+        credit_fees(deps.storage, ledger, &fund.denom, fees_collected)?;
+        if ledger == Ledger::Native {
+            record_deposit(deps.storage, &fund.denom, fund.amount, fees_collected)?;
+        }
+
+        result.amount.push(coin(amount.u128(), &fund.denom));
+        result
+            .fees_collected
+            .push(coin(fees_collected.u128(), &fund.denom));
+    }
+
+    Ok(result)
+}
+
+/// Credits every share of `split` to its recipient's wallet or, in push mode, sends it to them
+/// right away. Pushed shares are tracked in `PENDING_PAYOUTS` until their reply comes back so
+/// a failed send can still be credited. Vesting shares become grants in `VESTING_GRANTS` and
+/// expiring shares are held in `ESCROWS` until claimed instead. CW20 shares are always credited.
+/// Every split is recorded in `splits()` first, and escrows share the split's id. Returns the
+/// messages and attributes the caller must add to its response.
+fn settle_shares(
+    storage: &mut dyn Storage,
+    env: &Env,
+    split: &SplitResult,
+    options: SplitOptions,
+) -> Result<(Vec<SubMsg>, Vec<Attribute>), ContractError> {
+    if split.ledger == Ledger::Native {
+        if let Some(expires) = options.expires {
+            // escrowed shares are claimed into the wallet, they can't also vest
+            if options.vesting.is_some()
+                || matches!(expires, Expiration::Never {})
+                || expires.is_expired(&env.block)
+            {
+                return Err(ContractError::InvalidParams {});
+            }
+        }
+        if let Some(vesting) = &options.vesting {
+            vesting.validate()?;
+        }
+    }
+
+    let id = record_split(storage, env, split)?;
+    let attributes = vec![attr("split_id", id.to_string())];
+    if split.ledger == Ledger::Cw20 {
+        credit_shares(storage, split)?;
+        return Ok((vec![], attributes));
+    }
+    if let Some(expires) = options.expires {
+        let escrow = Escrow {
+            id,
+            sender: split.sender.clone(),
+            expires,
+            shares: split
+                .shares
+                .iter()
+                .map(|(recipient, share)| EscrowShare {
+                    recipient: recipient.clone(),
+                    amount: share.clone(),
+                    claimed: false,
+                })
+                .collect(),
+        };
+        ESCROWS.save(storage, id, &escrow)?;
+        return Ok((vec![], attributes));
+    }
+    if let Some(vesting) = options.vesting {
+        for (addr, share) in split.shares.iter() {
+            let count = VESTING_GRANT_COUNTS
+                .may_load(storage, addr.clone())?
+                .unwrap_or_default()
+                + share.len() as u32;
+            if count > MAX_VESTING_GRANTS {
+                return Err(ContractError::TooManyVestingGrants {
+                    recipient: addr.to_string(),
+                });
+            }
+            VESTING_GRANT_COUNTS.save(storage, addr.clone(), &count)?;
+            for c in share.iter() {
+                let id = GRANT_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+                GRANT_SEQ.save(storage, &id)?;
+                let grant = VestingGrant {
+                    denom: c.denom.clone(),
+                    total: c.amount,
+                    released: Uint128::zero(),
+                    vesting: vesting.clone(),
+                };
+                VESTING_GRANTS.save(storage, (addr.clone(), id), &grant)?;
+            }
+        }
+        return Ok((vec![], attributes));
+    }
+
+    let push = match options.push {
+        Some(push) => push,
+        None => CONFIG.load(storage)?.push_payouts,
+    };
+    if !push {
+        credit_shares(storage, split)?;
+        return Ok((vec![], attributes));
+    }
+
+    let mut payouts = vec![];
+    for (addr, share) in split.shares.iter() {
+        let amount = share.clone();
+        let id = PAYOUT_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+        PAYOUT_SEQ.save(storage, &id)?;
+        PENDING_PAYOUTS.save(
+            storage,
+            id,
+            &PendingPayout {
+                recipient: addr.clone(),
+                amount: amount.clone(),
+            },
+        )?;
+        let send = BankMsg::Send {
+            to_address: addr.to_string(),
+            amount,
+        };
+        payouts.push(SubMsg::reply_always(send, id));
+    }
+    Ok((payouts, attributes))
+}
+
+/// Saves the provenance of a split and returns its id
+fn record_split(storage: &mut dyn Storage, env: &Env, split: &SplitResult) -> StdResult<u64> {
+    let id = SPLIT_SEQ.may_load(storage)?.unwrap_or_default() + 1;
+    SPLIT_SEQ.save(storage, &id)?;
+    let record = SplitRecord {
+        id,
+        sender: split.sender.clone(),
+        token: match split.ledger {
+            Ledger::Native => None,
+            Ledger::Cw20 => split.amount.first().map(|c| Addr::unchecked(&c.denom)),
+        },
+        amount: split.amount.clone(),
+        fees: split.fees_collected.clone(),
+        shares: split
+            .shares
+            .iter()
+            .map(|(recipient, amount)| Share {
+                recipient: recipient.clone(),
+                amount: amount.clone(),
+            })
+            .collect(),
+        height: env.block.height,
+        time: env.block.time,
+    };
+    splits().save(storage, id, &record)?;
+    for (recipient, _) in split.shares.iter() {
+        SPLITS_BY_RECIPIENT.save(storage, (recipient.clone(), id), &Empty {})?;
+    }
+    Ok(id)
+}
+
+fn credit_shares(storage: &mut dyn Storage, split: &SplitResult) -> StdResult<()> {
+    for (addr, share) in split.shares.iter() {
+        for c in share.iter() {
+            credit_wallet(storage, split.ledger, addr, &c.denom, c.amount)?;
+        }
+    }
+    Ok(())
+}
+
+/// Moves whatever has vested of `addr`'s grants in `denom` into their wallet, dropping finished
+/// grants. Grants in other denoms are left for the withdrawals of those denoms.
+fn release_vested(storage: &mut dyn Storage, env: &Env, addr: &Addr, denom: &str) -> StdResult<()> {
+    let grants = VESTING_GRANTS
+        .prefix(addr.clone())
+        .range(storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, grant)) if grant.denom != denom))
+        .collect::<StdResult<Vec<_>>>()?;
+    for (id, mut grant) in grants {
+        let vested = grant.vesting.vested(grant.total, env.block.time);
+        if vested == grant.released {
+            continue;
+        }
+        credit_wallet(
+            storage,
+            Ledger::Native,
+            addr,
+            &grant.denom,
+            vested - grant.released,
+        )?;
+        grant.released = vested;
+        if grant.released == grant.total {
+            VESTING_GRANTS.remove(storage, (addr.clone(), id));
+            let count = VESTING_GRANT_COUNTS
+                .may_load(storage, addr.clone())?
+                .unwrap_or_default()
+                .saturating_sub(1);
+            if count == 0 {
+                VESTING_GRANT_COUNTS.remove(storage, addr.clone());
+            } else {
+                VESTING_GRANT_COUNTS.save(storage, addr.clone(), &count)?;
+            }
+        } else {
+            VESTING_GRANTS.save(storage, (addr.clone(), id), &grant)?;
+        }
+    }
+    Ok(())
+}
+
+fn credit_wallet(
+    storage: &mut dyn Storage,
+    ledger: Ledger,
+    addr: &Addr,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    // crediting nothing must not leave an empty entry behind
+    if amount.is_zero() {
+        return Ok(amount);
+    }
+    let add = |balance: Option<Uint128>| -> StdResult<_> {
+        Ok(balance.unwrap_or_default().checked_add(amount)?)
+    };
+    match ledger {
+        Ledger::Native => WALLETS.update(storage, (addr.clone(), denom.to_string()), add),
+        Ledger::Cw20 => CW20_WALLETS.update(storage, (addr.clone(), Addr::unchecked(denom)), add),
+    }
+}
+
+/// Accrues fees to the pool that `WithdrawFees` pays out from
+fn credit_fees(
+    storage: &mut dyn Storage,
+    ledger: Ledger,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    if amount.is_zero() {
+        return Ok(amount);
+    }
+    let add = |accrued: Option<Uint128>| -> StdResult<_> {
+        Ok(accrued.unwrap_or_default().checked_add(amount)?)
+    };
+    match ledger {
+        Ledger::Native => FEES.update(storage, denom.to_string(), add),
+        Ledger::Cw20 => CW20_FEES.update(storage, Addr::unchecked(denom), add),
+    }
+}
+
+/// Adds a native deposit to the running totals; everything deposited is owed until paid out
+fn record_deposit(
+    storage: &mut dyn Storage,
+    denom: &str,
+    amount: Uint128,
+    fee: Uint128,
+) -> StdResult<()> {
+    let mut totals = TOTALS
+        .may_load(storage, denom.to_string())?
+        .unwrap_or_default();
+    totals.deposited = totals.deposited.checked_add(amount)?;
+    totals.owed = totals.owed.checked_add(amount)?;
+    totals.fees_accrued = totals.fees_accrued.checked_add(fee)?;
+    TOTALS.save(storage, denom.to_string(), &totals)
+}
+
+/// Takes native coins leaving the contract off the running totals
+fn record_payout(storage: &mut dyn Storage, amount: &[Coin]) -> StdResult<()> {
+    for c in amount.iter() {
+        let mut totals = TOTALS
+            .may_load(storage, c.denom.clone())?
+            .unwrap_or_default();
+        // paying out more than is owed means the ledger has drifted
+        totals.owed = totals.owed.checked_sub(c.amount)?;
+        totals.withdrawn = totals.withdrawn.checked_add(c.amount)?;
+        TOTALS.save(storage, c.denom.clone(), &totals)?;
+    }
+    Ok(())
+}
+
+/// Refuses to pay anything out of `denom` once the bank balance no longer covers what is owed
+fn assert_solvent(deps: Deps, env: &Env, denom: &str) -> Result<(), ContractError> {
+    let owed = TOTALS
+        .may_load(deps.storage, denom.to_string())?
+        .unwrap_or_default()
+        .owed;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, denom)?
+        .amount;
+    if balance < owed {
+        return Err(ContractError::Insolvent {
+            denom: denom.to_string(),
+            owed,
+            balance,
+        });
+    }
+    Ok(())
+}
+
+/// Formats coins as a comma separated list for response attributes (e.g. "50usei,10uatom")
+fn coins_to_string(coins: &[Coin]) -> String {
+    coins
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Fee `sender` pays on a split of `coin`, after any fee exemption they hold
+fn fee_for_sender(deps: Deps, config: &Config, sender: &Addr, coin: &Coin) -> StdResult<Uint128> {
+    let fee = config.fee_schedule.fee_for(coin, config.max_fee_bps);
+    Ok(
+        match FEE_EXEMPTIONS.may_load(deps.storage, sender.clone())? {
+            Some(discount_bps) => apply_discount(fee, discount_bps),
+            None => fee,
+        },
+    )
+}
+
+/// Validates the recipients of a split: the contract itself is never one, and the configured
+/// `RecipientPolicy` decides on the sender and on duplicates, merging them if it says so
+fn check_recipients(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    sender: &Addr,
+    recipients: Vec<Recipient>,
+) -> Result<Vec<(Addr, u64)>, ContractError> {
+    let policy = &config.recipient_policy;
+    let mut checked: Vec<(Addr, u64)> = vec![];
+    for recipient in recipients {
+        let addr = deps.api.addr_validate(&recipient.addr)?;
+        if addr == env.contract.address {
+            return Err(ContractError::ContractRecipient {});
+        }
+        if policy.forbid_self && addr == *sender {
+            return Err(ContractError::SelfRecipient {});
+        }
+        match checked.iter_mut().find(|(a, _)| *a == addr) {
+            Some(_) if policy.duplicates == DuplicatePolicy::Reject => {
+                return Err(ContractError::DuplicateRecipient {
+                    recipient: addr.to_string(),
+                })
+            }
+            Some((_, weight)) if policy.duplicates == DuplicatePolicy::Merge => {
+                *weight = weight
+                    .checked_add(recipient.weight)
+                    .ok_or(ContractError::InvalidParams {})?;
+            }
+            _ => checked.push((addr, recipient.weight)),
+        }
+    }
+    Ok(checked)
+}
+
+/// Rounds every share down, returning the shares and the units left over
+fn weighted_shares(
+    amount: Uint128,
+    weights: &[u64],
+) -> Result<(Vec<Uint128>, Uint128), ContractError> {
+    if weights.contains(&0) {
+        return Err(ContractError::InvalidParams {});
+    }
+    let total_weight: u128 = weights.iter().map(|w| *w as u128).sum();
+
+    let shares: Vec<Uint128> = weights
+        .iter()
+        .map(|w| amount.multiply_ratio(*w, total_weight))
+        .collect();
+    let distributed: Uint128 = shares.iter().sum();
+
+    Ok((shares, amount - distributed))
+}
+
+/// Hands out `remainder` one unit at a time starting at `start`, wrapping around
+fn spread_remainder(shares: &mut [Uint128], start: usize, remainder: Uint128) {
+    let len = shares.len();
+    for i in 0..remainder.u128() as usize {
+        shares[(start + i) % len] += Uint128::new(1);
+    }
+}
+
+fn load_bucket(storage: &dyn Storage, ledger: Ledger, denom: &str) -> StdResult<Uint128> {
+    let held = match ledger {
+        Ledger::Native => REMAINDERS.may_load(storage, denom.to_string())?,
+        Ledger::Cw20 => CW20_REMAINDERS.may_load(storage, Addr::unchecked(denom))?,
+    };
+    Ok(held.unwrap_or_default())
+}
+
+fn save_bucket(
+    storage: &mut dyn Storage,
+    ledger: Ledger,
+    denom: &str,
+    amount: Uint128,
+) -> StdResult<()> {
+    match ledger {
+        Ledger::Native => REMAINDERS.save(storage, denom.to_string(), &amount),
+        Ledger::Cw20 => CW20_REMAINDERS.save(storage, Addr::unchecked(denom), &amount),
+    }
+}
+
+pub fn execute_withdraw_coins(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    amount: Option<Uint128>,
+    denom: Option<String>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // if amount is None, assume the caller wants to withdraw all coins at info.sender
+    if amount.is_some() && amount.unwrap() == Uint128::new(0) {
+        return Err(ContractError::InvalidParams {});
+    }
+    // if denom is None, assume the caller wants to withdraw usei
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+    // if recipient is None, the coins are sent to the caller
+    let recipient = match recipient {
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None => info.sender.clone(),
+    };
+    assert_not_contract(&env, &recipient)?;
+
+    release_vested(deps.storage, &env, &info.sender, &denom)?;
+    assert_solvent(deps.as_ref(), &env, &denom)?;
+    let withdraw_amount = debit_wallet(deps.storage, Ledger::Native, &info.sender, &denom, amount)?;
+    let payout = coins(withdraw_amount.u128(), &denom);
+    record_payout(deps.storage, &payout)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: payout,
+        })
+        .add_attribute("action", "WithdrawCoins")
+        .add_attribute("addr", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", withdraw_amount)
+        .add_attribute("denom", denom))
+}
+
+pub fn execute_increase_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    amount: Uint128,
+    denom: Option<String>,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let spender = deps.api.addr_validate(&spender)?;
+    if spender == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    if amount.is_zero() || expires.map_or(false, |e| e.is_expired(&env.block)) {
+        return Err(ContractError::InvalidParams {});
+    }
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+
+    let key = (info.sender.clone(), spender.clone(), denom.clone());
+    let allowance = ALLOWANCES.update(deps.storage, key, |allow| -> StdResult<_> {
+        let mut allow = allow.unwrap_or_default();
+        // an expired allowance starts over rather than being topped up
+        if allow.expires.is_expired(&env.block) {
+            allow = AllowanceResponse::default();
+        }
+        if let Some(expires) = expires {
+            allow.expires = expires;
+        }
+        allow.allowance = allow.allowance.checked_add(amount)?;
+        Ok(allow)
+    })?;
+
+    Ok(Response::new()
+        .add_attribute("action", "IncreaseAllowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("denom", denom)
+        .add_attribute("allowance", allowance.allowance)
+        .add_attribute("expires", allowance.expires.to_string()))
+}
+
+pub fn execute_decrease_allowance(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: String,
+    amount: Uint128,
+    denom: Option<String>,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let spender = deps.api.addr_validate(&spender)?;
+    if spender == info.sender {
+        return Err(ContractError::CannotSetOwnAccount {});
+    }
+    if amount.is_zero() || expires.map_or(false, |e| e.is_expired(&env.block)) {
+        return Err(ContractError::InvalidParams {});
+    }
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+
+    let key = (info.sender.clone(), spender.clone(), denom.clone());
+    let mut allowance = ALLOWANCES
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NoAllowance {})?;
+    // decreasing below zero simply revokes the allowance
+    allowance.allowance = allowance.allowance.saturating_sub(amount);
+    if allowance.allowance.is_zero() {
+        ALLOWANCES.remove(deps.storage, key);
+    } else {
+        if let Some(expires) = expires {
+            allowance.expires = expires;
+        }
+        ALLOWANCES.save(deps.storage, key, &allowance)?;
+    }
+
+    Ok(Response::new()
+        .add_attribute("action", "DecreaseAllowance")
+        .add_attribute("owner", info.sender)
+        .add_attribute("spender", spender)
+        .add_attribute("denom", denom)
+        .add_attribute("allowance", allowance.allowance))
+}
+
+pub fn execute_withdraw_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    amount: Option<Uint128>,
+    denom: Option<String>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    if amount.is_some() && amount.unwrap() == Uint128::new(0) {
+        return Err(ContractError::InvalidParams {});
+    }
+    let owner = deps.api.addr_validate(&owner)?;
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+    // if recipient is None, the coins are sent to the spender
+    let recipient = match recipient {
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None => info.sender.clone(),
+    };
+    assert_not_contract(&env, &recipient)?;
+
+    // resolve the whole balance up front so the allowance is checked before anything moves
+    release_vested(deps.storage, &env, &owner, &denom)?;
+    let withdraw_amount = match amount {
+        Some(amount) => amount,
+        None => WALLETS
+            .may_load(deps.storage, (owner.clone(), denom.clone()))?
+            .unwrap_or_default(),
+    };
+    if withdraw_amount.is_zero() {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    assert_solvent(deps.as_ref(), &env, &denom)?;
+    let key = (owner.clone(), info.sender.clone(), denom.clone());
+    spend_allowance(deps.storage, &env, key, withdraw_amount)?;
+    debit_wallet(
+        deps.storage,
+        Ledger::Native,
+        &owner,
+        &denom,
+        Some(withdraw_amount),
+    )?;
+    let payout = coins(withdraw_amount.u128(), &denom);
+    record_payout(deps.storage, &payout)?;
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: payout,
+        })
+        .add_attribute("action", "WithdrawFrom")
+        .add_attribute("owner", owner)
+        .add_attribute("spender", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", withdraw_amount)
+        .add_attribute("denom", denom))
+}
+
+pub fn execute_transfer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+    amount: Uint128,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidParams {});
+    }
+    let recipient = deps.api.addr_validate(&recipient)?;
+    assert_not_contract(&env, &recipient)?;
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+
+    release_vested(deps.storage, &env, &info.sender, &denom)?;
+    debit_wallet(
+        deps.storage,
+        Ledger::Native,
+        &info.sender,
+        &denom,
+        Some(amount),
+    )?;
+    credit_wallet(deps.storage, Ledger::Native, &recipient, &denom, amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "Transfer")
+        .add_attribute("from", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("denom", denom))
+}
+
+pub fn execute_transfer_from(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    owner: String,
+    recipient: String,
+    amount: Uint128,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::InvalidParams {});
+    }
+    let owner = deps.api.addr_validate(&owner)?;
+    let recipient = deps.api.addr_validate(&recipient)?;
+    assert_not_contract(&env, &recipient)?;
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+
+    let key = (owner.clone(), info.sender.clone(), denom.clone());
+    spend_allowance(deps.storage, &env, key, amount)?;
+    release_vested(deps.storage, &env, &owner, &denom)?;
+    debit_wallet(deps.storage, Ledger::Native, &owner, &denom, Some(amount))?;
+    credit_wallet(deps.storage, Ledger::Native, &recipient, &denom, amount)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "TransferFrom")
+        .add_attribute("from", owner)
+        .add_attribute("spender", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", amount)
+        .add_attribute("denom", denom))
+}
+
+pub fn execute_batch_payout(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    addrs: Option<Vec<String>>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_operator(&config, &info.sender)?;
+
+    // either an explicit list of accounts or the next page of funded wallets
+    let (addrs, next) = match addrs {
+        Some(addrs) => {
+            // the cursor only pages through wallets, so it makes no sense with a list
+            if start_after.is_some() || limit.is_some() {
+                return Err(ContractError::InvalidParams {});
+            }
+            if addrs.is_empty() || addrs.len() > MAX_BATCH_PAYOUT {
+                return Err(ContractError::InvalidParams {});
+            }
+            let addrs = addrs
+                .iter()
+                .map(|addr| deps.api.addr_validate(addr))
+                .collect::<StdResult<Vec<_>>>()?;
+            (addrs, None)
+        }
+        None => {
+            if limit == Some(0) {
+                return Err(ContractError::InvalidParams {});
+            }
+            let limit = limit
+                .map(|l| l as usize)
+                .unwrap_or(MAX_BATCH_PAYOUT)
+                .min(MAX_BATCH_PAYOUT);
+            let start_after = start_after
+                .map(|addr| deps.api.addr_validate(&addr))
+                .transpose()?;
+            let balances = WALLETS
+                .prefix_range(
+                    deps.storage,
+                    start_after.map(PrefixBound::exclusive),
+                    None,
+                    Order::Ascending,
+                )
+                .map(|item| item.map(|((addr, _), amount)| (addr, amount)));
+            let mut addrs = vec![];
+            collect_funded_owners(balances, limit, &mut addrs)?;
+            // a full page may have more wallets behind it
+            let next = if addrs.len() == limit {
+                addrs.last().cloned()
+            } else {
+                None
+            };
+            (addrs, next)
+        }
+    };
+
+    let mut res = Response::new().add_attribute("action", "BatchPayout");
+    let mut paid = 0;
+    // vesting grants are left alone, so each account costs a bounded amount of gas
+    for addr in addrs {
+        let balances = WALLETS
+            .prefix(addr.clone())
+            .range(deps.storage, None, None, Order::Ascending)
+            .collect::<StdResult<Vec<_>>>()?;
+        let amount: Vec<Coin> = balances
+            .iter()
+            .filter(|(_, amount)| !amount.is_zero())
+            .map(|(denom, amount)| coin(amount.u128(), denom))
+            .collect();
+        for c in amount.iter() {
+            assert_solvent(deps.as_ref(), &env, &c.denom)?;
+        }
+        for (denom, _) in balances {
+            WALLETS.remove(deps.storage, (addr.clone(), denom));
+        }
+        record_payout(deps.storage, &amount)?;
+        // accounts with nothing to withdraw are skipped
+        if amount.is_empty() {
+            continue;
+        }
+        res = res
+            .add_attribute("recipient", addr.clone())
+            .add_attribute("recipient_amount", coins_to_string(&amount))
+            .add_message(BankMsg::Send {
+                to_address: addr.to_string(),
+                amount,
+            });
+        paid += 1;
+    }
+
+    res = res.add_attribute("paid", paid.to_string());
+    if let Some(next) = next {
+        res = res.add_attribute("next", next);
+    }
+    Ok(res)
+}
+
+/// Deducts `amount` from the (owner, spender, denom) allowance, removing it once used up
+fn spend_allowance(
+    storage: &mut dyn Storage,
+    env: &Env,
+    key: (Addr, Addr, String),
+    amount: Uint128,
+) -> Result<(), ContractError> {
+    let mut allowance = ALLOWANCES
+        .may_load(storage, key.clone())?
+        .ok_or(ContractError::NoAllowance {})?;
+    if allowance.expires.is_expired(&env.block) {
+        return Err(ContractError::AllowanceExpired {});
+    }
+    allowance.allowance = allowance
+        .allowance
+        .checked_sub(amount)
+        .map_err(|_| ContractError::NoAllowance {})?;
+    if allowance.allowance.is_zero() {
+        ALLOWANCES.remove(storage, key);
+    } else {
+        ALLOWANCES.save(storage, key, &allowance)?;
+    }
+    Ok(())
+}
+
+/// Takes `amount` of `denom` (the whole balance if None) out of `addr`'s wallet on `ledger`
+fn debit_wallet(
+    storage: &mut dyn Storage,
+    ledger: Ledger,
+    addr: &Addr,
+    denom: &str,
+    amount: Option<Uint128>,
+) -> Result<Uint128, ContractError> {
+    let balance = match ledger {
+        Ledger::Native => WALLETS.may_load(storage, (addr.clone(), denom.to_string()))?,
+        Ledger::Cw20 => CW20_WALLETS.may_load(storage, (addr.clone(), Addr::unchecked(denom)))?,
+    }
+    .ok_or(ContractError::InsufficientFunds {})?;
+    // if amount is None, the whole balance is withdrawn
+    let withdraw_amount = amount.unwrap_or(balance);
+    let balance = balance
+        .checked_sub(withdraw_amount)
+        .map_err(|_| ContractError::InsufficientFunds {})?;
+    // drop emptied wallets rather than keep zero balances around
+    match ledger {
+        Ledger::Native if balance.is_zero() => {
+            WALLETS.remove(storage, (addr.clone(), denom.to_string()))
+        }
+        Ledger::Native => WALLETS.save(storage, (addr.clone(), denom.to_string()), &balance)?,
+        Ledger::Cw20 if balance.is_zero() => {
+            CW20_WALLETS.remove(storage, (addr.clone(), Addr::unchecked(denom)))
+        }
+        Ledger::Cw20 => {
+            CW20_WALLETS.save(storage, (addr.clone(), Addr::unchecked(denom)), &balance)?
+        }
+    }
+    Ok(withdraw_amount)
+}
+
+pub fn execute_withdraw_cw20(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    token: String,
+    amount: Option<Uint128>,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    // if amount is None, assume the caller wants to withdraw the entire token balance
+    if amount.is_some() && amount.unwrap() == Uint128::new(0) {
+        return Err(ContractError::InvalidParams {});
+    }
+    let token = deps.api.addr_validate(&token)?;
+    let recipient = match recipient {
+        Some(addr) => deps.api.addr_validate(&addr)?,
+        None => info.sender.clone(),
+    };
+    assert_not_contract(&env, &recipient)?;
+
+    let withdraw_amount = debit_wallet(
+        deps.storage,
+        Ledger::Cw20,
+        &info.sender,
+        token.as_str(),
+        amount,
+    )?;
+
+    Ok(Response::new()
+        .add_message(WasmMsg::Execute {
+            contract_addr: token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: withdraw_amount,
+            })?,
+            funds: vec![],
+        })
+        .add_attribute("action", "WithdrawCw20")
+        .add_attribute("addr", info.sender)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", withdraw_amount)
+        .add_attribute("token", token))
+}
+
+pub fn execute_withdraw_fees(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    token: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    // if recipient is None, pay the fees out to the configured fee collector
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => config.fee_collector,
+    };
+    assert_not_contract(&env, &recipient)?;
+
+    let res = Response::new()
+        .add_attribute("action", "WithdrawFees")
+        .add_attribute("recipient", recipient.clone());
+
+    if let Some(token) = token {
+        let token = deps.api.addr_validate(&token)?;
+        let amount = CW20_FEES
+            .may_load(deps.storage, token.clone())?
+            .unwrap_or_default();
+        if amount.is_zero() {
+            return Err(ContractError::InsufficientFunds {});
+        }
+        CW20_FEES.remove(deps.storage, token.clone());
+        return Ok(res
+            .add_message(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount,
+                })?,
+                funds: vec![],
+            })
+            .add_attribute("amount", amount)
+            .add_attribute("token", token));
+    }
+
+    let accrued = accrued_fees(deps.as_ref())?;
+    if accrued.is_empty() {
+        return Err(ContractError::InsufficientFunds {});
+    }
+    for fee in accrued.iter() {
+        assert_solvent(deps.as_ref(), &env, &fee.denom)?;
+    }
+    for fee in accrued.iter() {
+        FEES.remove(deps.storage, fee.denom.clone());
+    }
+    record_payout(deps.storage, &accrued)?;
+
+    Ok(res
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: accrued.clone(),
+        })
+        .add_attribute("amount", coins_to_string(&accrued)))
+}
+
+pub fn execute_sweep_surplus(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Option<String>,
+    denom: Option<String>,
+) -> Result<Response, ContractError> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    assert_owner(&config, &info.sender)?;
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => info.sender,
+    };
+    assert_not_contract(&env, &recipient)?;
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+
+    // only coins no ledger accounts for are swept, so the totals are left alone
+    let owed = TOTALS
+        .may_load(deps.storage, denom.clone())?
+        .unwrap_or_default()
+        .owed;
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount;
+    let surplus = balance.saturating_sub(owed);
+    if surplus.is_zero() {
+        return Err(ContractError::InsufficientFunds {});
+    }
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: coins(surplus.u128(), &denom),
+        })
+        .add_attribute("action", "SweepSurplus")
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", surplus)
+        .add_attribute("denom", denom))
+}
+
+/// Settles a push payout - a share that could not be sent is credited to the recipient's wallet
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let payout = PENDING_PAYOUTS.load(deps.storage, msg.id)?;
+    PENDING_PAYOUTS.remove(deps.storage, msg.id);
+
+    let res = Response::new()
+        .add_attribute("recipient", payout.recipient.clone())
+        .add_attribute("amount", coins_to_string(&payout.amount));
+    match msg.result {
+        SubMsgResult::Ok(_) => {
+            record_payout(deps.storage, &payout.amount)?;
+            Ok(res.add_attribute("action", "PayoutSent"))
+        }
+        SubMsgResult::Err(err) => {
+            for c in payout.amount.iter() {
+                credit_wallet(
+                    deps.storage,
+                    Ledger::Native,
+                    &payout.recipient,
+                    &c.denom,
+                    c.amount,
+                )?;
+            }
+            Ok(res
+                .add_attribute("action", "PayoutFailed")
+                .add_attribute("error", err))
+        }
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract != CONTRACT_NAME {
+        return Err(ContractError::InvalidMigrationContract {
+            contract: stored.contract,
+        });
+    }
+    let stored_version = parse_version(&stored.version)?;
+    if stored_version > parse_version(CONTRACT_VERSION)? {
+        return Err(ContractError::MigrationDowngrade {
+            stored: stored.version,
+            current: CONTRACT_VERSION.to_string(),
+        });
+    }
+
+    // state migrations run in order, each bringing storage up to the next layout
+    if stored_version < (2, 0, 0) {
+        migrate_from_v1(deps.storage)?;
+    }
+    if stored_version < (2, 1, 0) {
+        seed_totals(deps.storage)?;
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    Ok(Response::new()
+        .add_attribute("method", "migrate")
+        .add_attribute("from_version", stored.version)
+        .add_attribute("to_version", CONTRACT_VERSION))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetOwner {} => to_binary(&query_owner(deps)?),
+        QueryMsg::PauseStatus {} => to_binary(&query_pause_status(deps)?),
+        QueryMsg::Solvency { denom } => to_binary(&query_solvency(deps, env, denom)?),
+        QueryMsg::GetWallet { addr } => to_binary(&query_wallet(deps, addr)?),
+        QueryMsg::ListWallets { start_after, limit } => {
+            to_binary(&query_list_wallets(deps, start_after, limit)?)
+        }
+        QueryMsg::GetFee { amount, sender } => to_binary(&query_fee(deps, amount, sender)?),
+        QueryMsg::Allowance {
+            owner,
+            spender,
+            denom,
+        } => to_binary(&query_allowance(deps, owner, spender, denom)?),
+        QueryMsg::AllAllowances {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_all_allowances(deps, owner, start_after, limit)?),
+        QueryMsg::GetSplit { split_id } => to_binary(&splits().load(deps.storage, split_id)?),
+        QueryMsg::ListSplitsBySender {
+            sender,
+            start_after,
+            limit,
+        } => to_binary(&query_splits_by_sender(deps, sender, start_after, limit)?),
+        QueryMsg::ListSplitsByRecipient {
+            recipient,
+            start_after,
+            limit,
+        } => to_binary(&query_splits_by_recipient(
+            deps,
+            recipient,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::GetEscrow { split_id } => to_binary(&ESCROWS.load(deps.storage, split_id)?),
+        QueryMsg::ListEscrows { start_after, limit } => {
+            to_binary(&query_escrows(deps, start_after, limit)?)
+        }
+        QueryMsg::GetSplitGroup { group_id } => {
+            to_binary(&SPLIT_GROUPS.load(deps.storage, group_id)?)
+        }
+        QueryMsg::ListSplitGroups { start_after, limit } => {
+            to_binary(&query_split_groups(deps, start_after, limit)?)
+        }
+        QueryMsg::GetVesting { addr } => to_binary(&query_vesting(deps, env, addr)?),
+        QueryMsg::ListFeeExemptions { start_after, limit } => {
+            to_binary(&query_fee_exemptions(deps, start_after, limit)?)
+        }
+    }
+}
+
+fn query_owner(deps: Deps) -> StdResult<OwnerResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let pending = PENDING_OWNER.may_load(deps.storage)?;
+    Ok(OwnerResponse {
+        owner: config.owner,
+        pending_owner: pending.as_ref().map(|p| p.new_owner.clone()),
+        pending_expiry: pending.and_then(|p| p.expiry),
+    })
+}
+
+fn query_pause_status(deps: Deps) -> StdResult<PauseStatusResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let paused = PAUSED.may_load(deps.storage)?.unwrap_or_default();
+    Ok(PauseStatusResponse {
+        splits_paused: paused.splits,
+        withdrawals_paused: paused.withdrawals,
+        guardian: config.guardian,
+    })
+}
+
+fn query_solvency(deps: Deps, env: Env, denom: Option<String>) -> StdResult<SolvencyResponse> {
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+    let totals = TOTALS
+        .may_load(deps.storage, denom.clone())?
+        .unwrap_or_default();
+    let balance = deps
+        .querier
+        .query_balance(&env.contract.address, &denom)?
+        .amount;
+    Ok(SolvencyResponse {
+        denom,
+        balance,
+        solvent: balance >= totals.owed,
+        totals,
+    })
+}
+
+fn query_wallet(deps: Deps, addr: Addr) -> StdResult<WalletResponse> {
+    let balances = WALLETS
+        .prefix(addr.clone())
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, amount)) if amount.is_zero()))
+        .map(|item| item.map(|(denom, amount)| coin(amount.u128(), denom)))
+        .collect::<StdResult<_>>()?;
+    let cw20_balances = CW20_WALLETS
+        .prefix(addr.clone())
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, amount)) if amount.is_zero()))
+        .map(|item| item.map(|(address, amount)| Cw20CoinVerified { address, amount }))
+        .collect::<StdResult<_>>()?;
+    Ok(WalletResponse {
+        addr,
+        balances,
+        cw20_balances,
+    })
+}
+
+fn query_list_wallets(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<ListWalletsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?;
+
+    // a wallet may hold native coins, CW20 tokens or both, so take the first `limit` funded
+    // owners of each ledger and merge them
+    let mut owners = Vec::with_capacity(limit * 2);
+    let native = WALLETS.prefix_range(
+        deps.storage,
+        start_after.clone().map(PrefixBound::exclusive),
+        None,
+        Order::Ascending,
+    );
+    collect_funded_owners(
+        native.map(|item| item.map(|((addr, _), amount)| (addr, amount))),
+        limit,
+        &mut owners,
+    )?;
+    let cw20 = CW20_WALLETS.prefix_range(
+        deps.storage,
+        start_after.map(PrefixBound::exclusive),
+        None,
+        Order::Ascending,
+    );
+    collect_funded_owners(
+        cw20.map(|item| item.map(|((addr, _), amount)| (addr, amount))),
+        limit,
+        &mut owners,
+    )?;
+    // composite keys are length-prefixed, so storage orders owners by length first
+    owners.sort_by(|a, b| (a.as_str().len(), a).cmp(&(b.as_str().len(), b)));
+    owners.dedup();
+    owners.truncate(limit);
+
+    let wallets = owners
+        .into_iter()
+        .map(|addr| query_wallet(deps, addr))
+        .collect::<StdResult<Vec<_>>>()?;
+    // a short page means there is nothing left to list
+    let next = if wallets.len() == limit {
+        wallets.last().map(|wallet| wallet.addr.clone())
+    } else {
+        None
+    };
+    Ok(ListWalletsResponse { wallets, next })
+}
+
+/// Appends up to `limit` distinct owners with a non-zero balance from an owner-ordered iterator
+fn collect_funded_owners(
+    mut balances: impl Iterator<Item = StdResult<(Addr, Uint128)>>,
+    limit: usize,
+    owners: &mut Vec<Addr>,
+) -> StdResult<()> {
+    let mut last: Option<Addr> = None;
+    let mut found = 0;
+    while found < limit {
+        let (addr, amount) = match balances.next() {
+            Some(item) => item?,
+            None => break,
+        };
+        if amount.is_zero() || last.as_ref() == Some(&addr) {
+            continue;
+        }
+        last = Some(addr.clone());
+        owners.push(addr);
+        found += 1;
+    }
+    Ok(())
+}
+
+fn query_fee(deps: Deps, amount: Option<Coin>, sender: Option<String>) -> StdResult<FeeResponse> {
+    let config: Config = CONFIG.load(deps.storage)?;
+    let simulated_fee = match (amount, sender) {
+        (Some(amount), Some(sender)) => {
+            let sender = deps.api.addr_validate(&sender)?;
+            let fee = fee_for_sender(deps, &config, &sender, &amount)?;
+            Some(coin(fee.u128(), amount.denom))
+        }
+        (Some(amount), None) => {
+            let fee = config.fee_schedule.fee_for(&amount, config.max_fee_bps);
+            Some(coin(fee.u128(), amount.denom))
+        }
+        (None, _) => None,
+    };
+
+    Ok(FeeResponse {
+        fee_collector: config.fee_collector,
+        simulated_fee,
+        fee_schedule: config.fee_schedule,
+        max_fee_bps: config.max_fee_bps,
+        accrued: accrued_fees(deps)?,
+        cw20_accrued: accrued_cw20_fees(deps)?,
+    })
+}
+
+fn query_allowance(
+    deps: Deps,
+    owner: String,
+    spender: String,
+    denom: Option<String>,
+) -> StdResult<AllowanceResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let spender = deps.api.addr_validate(&spender)?;
+    let denom = denom.unwrap_or_else(|| String::from(DEFAULT_DENOM));
+    Ok(ALLOWANCES
+        .may_load(deps.storage, (owner, spender, denom))?
+        .unwrap_or_default())
+}
+
+fn query_all_allowances(
+    deps: Deps,
+    owner: String,
+    start_after: Option<(String, String)>,
+    limit: Option<u32>,
+) -> StdResult<AllAllowancesResponse> {
+    let owner = deps.api.addr_validate(&owner)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|(spender, denom)| -> StdResult<_> {
+            Ok(Bound::exclusive((deps.api.addr_validate(&spender)?, denom)))
+        })
+        .transpose()?;
+
+    let allowances = ALLOWANCES
+        .sub_prefix(owner)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| {
+            item.map(|((spender, denom), allow)| AllowanceInfo {
+                spender,
+                denom,
+                allowance: allow.allowance,
+                expires: allow.expires,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(AllAllowancesResponse { allowances })
+}
+
+fn query_splits_by_sender(
+    deps: Deps,
+    sender: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SplitsResponse> {
+    let sender = deps.api.addr_validate(&sender)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let splits = splits()
+        .idx
+        .sender
+        .prefix(sender)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, record)| record))
+        .collect::<StdResult<_>>()?;
+    Ok(SplitsResponse { splits })
+}
+
+fn query_splits_by_recipient(
+    deps: Deps,
+    recipient: String,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SplitsResponse> {
+    let recipient = deps.api.addr_validate(&recipient)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let splits = SPLITS_BY_RECIPIENT
+        .prefix(recipient)
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|id| splits().load(deps.storage, id?))
+        .collect::<StdResult<_>>()?;
+    Ok(SplitsResponse { splits })
+}
+
+fn query_escrows(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<EscrowsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let escrows = ESCROWS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, escrow)| escrow))
+        .collect::<StdResult<_>>()?;
+    Ok(EscrowsResponse { escrows })
+}
+
+fn query_split_groups(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<SplitGroupsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let groups = SPLIT_GROUPS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, group)| group))
+        .collect::<StdResult<_>>()?;
+    Ok(SplitGroupsResponse { groups })
+}
+
+fn query_vesting(deps: Deps, env: Env, addr: String) -> StdResult<VestingResponse> {
+    let addr = deps.api.addr_validate(&addr)?;
+    let grants = VESTING_GRANTS
+        .prefix(addr.clone())
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            item.map(|(id, grant)| VestingGrantInfo {
+                id,
+                vested: grant.vesting.vested(grant.total, env.block.time) - grant.released,
+                denom: grant.denom,
+                total: grant.total,
+                released: grant.released,
+                vesting: grant.vesting,
+            })
+        })
+        .collect::<StdResult<_>>()?;
+    Ok(VestingResponse { addr, grants })
+}
+
+fn query_fee_exemptions(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<FeeExemptionsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|addr| deps.api.addr_validate(&addr))
+        .transpose()?
+        .map(Bound::exclusive);
+
+    let exemptions = FEE_EXEMPTIONS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(addr, discount_bps)| FeeExemption { addr, discount_bps }))
+        .collect::<StdResult<_>>()?;
+    Ok(FeeExemptionsResponse { exemptions })
+}
+
+/// Native fees collected by splits and not yet paid out by `WithdrawFees`
+fn accrued_fees(deps: Deps) -> StdResult<Vec<Coin>> {
+    FEES.range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, amount)) if amount.is_zero()))
+        .map(|item| item.map(|(denom, amount)| coin(amount.u128(), denom)))
+        .collect()
+}
+
+/// CW20 fees collected by splits and not yet paid out by `WithdrawFees`
+fn accrued_cw20_fees(deps: Deps) -> StdResult<Vec<Cw20CoinVerified>> {
+    CW20_FEES
+        .range(deps.storage, None, None, Order::Ascending)
+        .filter(|item| !matches!(item, Ok((_, amount)) if amount.is_zero()))
+        .map(|item| item.map(|(address, amount)| Cw20CoinVerified { address, amount }))
+        .collect()
 }
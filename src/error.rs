@@ -1,4 +1,4 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -18,4 +18,77 @@ pub enum ContractError {
 
     #[error("Invalid Token Transfer")]
     InvalidTokenTransfer {},
+
+    #[error("Denom {denom} is not allowed")]
+    DenomNotAllowed { denom: String },
+
+    #[error("CW20 token {token} is not allowed")]
+    TokenNotAllowed { token: String },
+
+    #[error("Fee of {fee_bps} bps exceeds the {max_fee_bps} bps cap")]
+    FeeTooHigh { fee_bps: u16, max_fee_bps: u16 },
+
+    #[error("No pending ownership transfer")]
+    NoPendingOwner {},
+
+    #[error("Ownership proposal expired")]
+    OwnershipProposalExpired {},
+
+    #[error("Cannot set allowance to own account")]
+    CannotSetOwnAccount {},
+
+    #[error("No allowance for this account")]
+    NoAllowance {},
+
+    #[error("Allowance is expired")]
+    AllowanceExpired {},
+
+    #[error("Claim period has expired")]
+    ClaimExpired {},
+
+    #[error("Claim period has not expired yet")]
+    ClaimNotExpired {},
+
+    #[error("Nothing to claim")]
+    NothingToClaim {},
+
+    #[error("Split group {group_id} is immutable")]
+    ImmutableGroup { group_id: u64 },
+
+    #[error("Cannot migrate from contract {contract}")]
+    InvalidMigrationContract { contract: String },
+
+    #[error("Cannot migrate from version {stored} down to {current}")]
+    MigrationDowngrade { stored: String, current: String },
+
+    #[error("Invalid contract version {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Contract is paused")]
+    Paused {},
+
+    #[error("Deposit is below the minimum of {min}{denom}")]
+    DepositTooSmall { denom: String, min: Uint128 },
+
+    #[error("Split is too small - {recipient} would receive nothing")]
+    ZeroShare { recipient: String },
+
+    #[error("Recipient {recipient} is listed more than once")]
+    DuplicateRecipient { recipient: String },
+
+    #[error("The contract itself cannot be a recipient")]
+    ContractRecipient {},
+
+    #[error("Sender cannot be a recipient of their own split")]
+    SelfRecipient {},
+
+    #[error("{recipient} already holds the maximum number of vesting grants")]
+    TooManyVestingGrants { recipient: String },
+
+    #[error("Contract holds {balance}{denom} but owes {owed}{denom}")]
+    Insolvent {
+        denom: String,
+        owed: Uint128,
+        balance: Uint128,
+    },
 }
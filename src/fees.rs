@@ -0,0 +1,151 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use cosmwasm_std::{Coin, Uint128};
+
+use crate::ContractError;
+
+// fees are expressed in basis points - 10_000 bps is 100%
+pub const BPS_DENOM: u16 = 10_000;
+
+/// How the fee on a split is computed. Every schedule is applied to each coin of a split
+/// separately. Fixed amounts only ever apply to coins of their own denom (for CW20 tokens, the
+/// token contract address).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum FeeSchedule {
+    /// A fixed fee per split of `amount.denom`; other denoms pay no fee
+    Flat { amount: Coin },
+    /// A percentage of the split, clamped to [min, max] for coins of the clamp's denom
+    Percentage {
+        bps: u16,
+        min: Option<Coin>,
+        max: Option<Coin>,
+    },
+    /// Volume pricing for `denom` - the tier with the highest threshold not above the split
+    /// amount applies. Tiers are sorted by threshold and the first one starts at 0. Other denoms
+    /// pay the first tier's rate.
+    Tiered { denom: String, tiers: Vec<FeeTier> },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeTier {
+    pub threshold: Uint128,
+    pub bps: u16,
+}
+
+impl FeeSchedule {
+    /// Checks the schedule is well formed and that no percentage in it exceeds `max_fee_bps`
+    pub fn validate(&self, max_fee_bps: u16) -> Result<(), ContractError> {
+        match self {
+            FeeSchedule::Flat { amount } => assert_denom(&amount.denom),
+            FeeSchedule::Percentage { bps, min, max } => {
+                for clamp in min.iter().chain(max.iter()) {
+                    assert_denom(&clamp.denom)?;
+                }
+                if let (Some(min), Some(max)) = (min, max) {
+                    if min.denom == max.denom && min.amount > max.amount {
+                        return Err(ContractError::InvalidParams {});
+                    }
+                }
+                assert_fee_within_cap(*bps, max_fee_bps)
+            }
+            FeeSchedule::Tiered { denom, tiers } => {
+                assert_denom(denom)?;
+                match tiers.first() {
+                    Some(first) if first.threshold.is_zero() => {}
+                    _ => return Err(ContractError::InvalidParams {}),
+                }
+                if tiers.windows(2).any(|w| w[0].threshold >= w[1].threshold) {
+                    return Err(ContractError::InvalidParams {});
+                }
+                tiers
+                    .iter()
+                    .try_for_each(|tier| assert_fee_within_cap(tier.bps, max_fee_bps))
+            }
+        }
+    }
+
+    /// Fee owed on a split of `coin`, in the same denom. Percentages always round down, in
+    /// favour of the sender, and the result never exceeds `max_fee_bps` of the amount - whatever
+    /// the schedule says.
+    pub fn fee_for(&self, coin: &Coin, max_fee_bps: u16) -> Uint128 {
+        let amount = coin.amount;
+        let fee = match self {
+            FeeSchedule::Flat { amount: flat } if flat.denom == coin.denom => flat.amount,
+            FeeSchedule::Flat { .. } => Uint128::zero(),
+            FeeSchedule::Percentage { bps, min, max } => {
+                let mut fee = bps_of(amount, *bps);
+                if let Some(min) = min.as_ref().filter(|min| min.denom == coin.denom) {
+                    fee = fee.max(min.amount);
+                }
+                if let Some(max) = max.as_ref().filter(|max| max.denom == coin.denom) {
+                    fee = fee.min(max.amount);
+                }
+                fee
+            }
+            FeeSchedule::Tiered { denom, tiers } => tiers
+                .iter()
+                .rev()
+                .find(|tier| *denom == coin.denom && amount >= tier.threshold)
+                .or_else(|| tiers.first())
+                .map(|tier| bps_of(amount, tier.bps))
+                .unwrap_or_default(),
+        };
+        fee.min(bps_of(amount, max_fee_bps))
+    }
+}
+
+/// Compact form used in response attributes, e.g. "percentage:25bps,min=10usei" or
+/// "tiered:0usei=30bps,1000usei=20bps"
+impl fmt::Display for FeeSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FeeSchedule::Flat { amount } => write!(f, "flat:{}", amount),
+            FeeSchedule::Percentage { bps, min, max } => {
+                write!(f, "percentage:{}bps", bps)?;
+                if let Some(min) = min {
+                    write!(f, ",min={}", min)?;
+                }
+                if let Some(max) = max {
+                    write!(f, ",max={}", max)?;
+                }
+                Ok(())
+            }
+            FeeSchedule::Tiered { denom, tiers } => {
+                let tiers: Vec<String> = tiers
+                    .iter()
+                    .map(|t| format!("{}{}={}bps", t.threshold, denom, t.bps))
+                    .collect();
+                write!(f, "tiered:{}", tiers.join(","))
+            }
+        }
+    }
+}
+
+/// Reduces `fee` by `discount_bps` (10_000 waives it entirely), rounding in favour of the sender
+pub fn apply_discount(fee: Uint128, discount_bps: u16) -> Uint128 {
+    fee.multiply_ratio(BPS_DENOM.saturating_sub(discount_bps), BPS_DENOM)
+}
+
+pub fn assert_fee_within_cap(fee_bps: u16, max_fee_bps: u16) -> Result<(), ContractError> {
+    if fee_bps > max_fee_bps {
+        return Err(ContractError::FeeTooHigh {
+            fee_bps,
+            max_fee_bps,
+        });
+    }
+    Ok(())
+}
+
+fn assert_denom(denom: &str) -> Result<(), ContractError> {
+    if denom.trim().is_empty() {
+        return Err(ContractError::InvalidParams {});
+    }
+    Ok(())
+}
+
+fn bps_of(amount: Uint128, bps: u16) -> Uint128 {
+    amount.multiply_ratio(bps, BPS_DENOM)
+}
@@ -1,8 +1,12 @@
 pub mod contract;
 mod error;
+pub mod fees;
+mod migrations;
 pub mod msg;
 pub mod state;
+pub mod vesting;
 
+#[allow(clippy::module_inception)]
 mod tests;
 
 pub use crate::error::ContractError;
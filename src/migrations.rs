@@ -0,0 +1,146 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Order, StdResult, Storage, Uint128};
+use cw_storage_plus::{Item, Map};
+
+use crate::contract::{DEFAULT_DENOM, DEFAULT_MAX_FEE_BPS};
+use crate::fees::FeeSchedule;
+use crate::msg::{RecipientPolicy, RemainderPolicy};
+use crate::state::{
+    Config, Totals, CONFIG, ESCROWS, FEES, PENDING_PAYOUTS, REMAINDERS, TOTALS, VESTING_GRANTS,
+    WALLETS,
+};
+use crate::ContractError;
+
+/// Layout of the 1.x releases: a whole-percent fee, a mandatory owner and usei-only wallets
+mod v1 {
+    use super::*;
+
+    #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+    pub struct Config {
+        pub owner: Addr,
+        pub cw20_addr: Addr,
+        pub fee_percent: Uint128,
+    }
+
+    pub const CONFIG: Item<Config> = Item::new("config");
+
+    pub const WALLETS: Map<Addr, Uint128> = Map::new("wallets");
+}
+
+/// Parses "major.minor.patch", ignoring any pre-release or build suffix
+pub fn parse_version(version: &str) -> Result<(u64, u64, u64), ContractError> {
+    let core = version.split(['-', '+']).next().unwrap_or_default();
+    let parts = core
+        .split('.')
+        .map(|part| part.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| ContractError::InvalidVersion {
+            version: version.to_string(),
+        })?;
+    match parts[..] {
+        [major, minor, patch] => Ok((major, minor, patch)),
+        _ => Err(ContractError::InvalidVersion {
+            version: version.to_string(),
+        }),
+    }
+}
+
+/// Rewrites 1.x state into the 2.0 layout
+pub fn migrate_from_v1(storage: &mut dyn Storage) -> Result<(), ContractError> {
+    let legacy = v1::CONFIG.load(storage)?;
+    // fee_percent was capped at 100, so this can't overflow
+    let bps = legacy.fee_percent.u128() as u16 * 100;
+    let config = Config {
+        owner: Some(legacy.owner.clone()),
+        cw20_addr: legacy.cw20_addr,
+        allowed_cw20s: vec![],
+        fee_collector: legacy.owner,
+        fee_schedule: FeeSchedule::Percentage {
+            bps,
+            min: None,
+            max: None,
+        },
+        // keep existing fees valid if they were set above today's default cap
+        max_fee_bps: DEFAULT_MAX_FEE_BPS.max(bps),
+        allowed_denoms: None,
+        push_payouts: false,
+        operators: vec![],
+        guardian: None,
+        remainder_policy: RemainderPolicy::First,
+        min_deposits: vec![],
+        recipient_policy: RecipientPolicy::default(),
+    };
+    CONFIG.save(storage, &config)?;
+
+    // 1.x balances were all usei, keyed by owner alone. Fees were credited to the wallet of
+    // cw20_addr, so that entry becomes the fee pool, and emptied wallets were left at zero.
+    let balances = v1::WALLETS
+        .range(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for (addr, amount) in balances {
+        v1::WALLETS.remove(storage, addr.clone());
+        if amount.is_zero() {
+            continue;
+        }
+        if addr == config.cw20_addr {
+            FEES.save(storage, DEFAULT_DENOM.to_string(), &amount)?;
+        } else {
+            WALLETS.save(storage, (addr, DEFAULT_DENOM.to_string()), &amount)?;
+        }
+    }
+    Ok(())
+}
+
+/// Rebuilds the 2.1 running totals from the native ledgers. History before the upgrade is
+/// unknown, so everything currently owed is counted as deposited.
+pub fn seed_totals(storage: &mut dyn Storage) -> StdResult<()> {
+    let mut owed: Vec<(String, Uint128)> = vec![];
+    let mut add = |denom: &str, amount: Uint128| match owed.iter_mut().find(|(d, _)| d == denom) {
+        Some((_, total)) => *total += amount,
+        None => owed.push((denom.to_string(), amount)),
+    };
+
+    for item in WALLETS.range(storage, None, None, Order::Ascending) {
+        let ((_, denom), amount) = item?;
+        add(&denom, amount);
+    }
+    for item in FEES.range(storage, None, None, Order::Ascending) {
+        let (denom, amount) = item?;
+        add(&denom, amount);
+    }
+    for item in REMAINDERS.range(storage, None, None, Order::Ascending) {
+        let (denom, amount) = item?;
+        add(&denom, amount);
+    }
+    for item in VESTING_GRANTS.range(storage, None, None, Order::Ascending) {
+        let (_, grant) = item?;
+        add(&grant.denom, grant.total - grant.released);
+    }
+    for item in ESCROWS.range(storage, None, None, Order::Ascending) {
+        let (_, escrow) = item?;
+        for share in escrow.shares.iter().filter(|s| !s.claimed) {
+            for c in share.amount.iter() {
+                add(&c.denom, c.amount);
+            }
+        }
+    }
+    for item in PENDING_PAYOUTS.range(storage, None, None, Order::Ascending) {
+        let (_, payout) = item?;
+        for c in payout.amount.iter() {
+            add(&c.denom, c.amount);
+        }
+    }
+
+    for (denom, amount) in owed {
+        let totals = Totals {
+            deposited: amount,
+            owed: amount,
+            fees_accrued: FEES.may_load(storage, denom.clone())?.unwrap_or_default(),
+            withdrawn: Uint128::zero(),
+        };
+        TOTALS.save(storage, denom, &totals)?;
+    }
+    Ok(())
+}
@@ -1,12 +1,48 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Coin, Uint128};
+use cw20::{Cw20CoinVerified, Cw20ReceiveMsg, Expiration};
+
+use crate::fees::FeeSchedule;
+use crate::state::{Escrow, SplitGroup, SplitRecord, Totals};
+use crate::vesting::Vesting;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::fmt;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct InstantiateMsg {
     pub owner: Option<String>,
     pub cw20_addr: String,
-    pub fee_percent: Option<Uint128>,
+    /// CW20 tokens accepted besides `cw20_addr`
+    pub allowed_cw20s: Option<Vec<String>>,
+    pub fee_collector: Option<String>,
+    /// Fee taken from every split, in basis points (100 bps = 1%); defaults to 0.
+    /// Shorthand for a `FeeSchedule::Percentage` without clamps - set at most one of the two.
+    pub fee_bps: Option<u16>,
+    pub fee_schedule: Option<FeeSchedule>,
+    /// Upper bound on any fee, in basis points of the split; defaults to 2_000 (20%) and can only
+    /// be lowered
+    pub max_fee_bps: Option<u16>,
+    /// Native denoms accepted by splits; None (or an empty list) accepts any denom
+    pub allowed_denoms: Option<Vec<String>>,
+    /// Default for `SplitOptions.push`; defaults to false
+    pub push_payouts: Option<bool>,
+    /// Default for `SplitOptions.remainder`; defaults to `RemainderPolicy::First`
+    pub remainder_policy: Option<RemainderPolicy>,
+    /// Smallest amount of each denom (or CW20 token address) a split accepts; unlisted denoms
+    /// have no minimum
+    pub min_deposits: Option<Vec<Coin>>,
+    /// Defaults to allowing duplicates and the sender
+    pub recipient_policy: Option<RecipientPolicy>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+/// A split destination; each recipient receives `weight / sum(weights)` of the split amount.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Recipient {
+    pub addr: String,
+    pub weight: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -15,9 +51,278 @@ pub enum ExecuteMsg {
     SplitCoins {
         target_addr1: String,
         target_addr2: String,
+        options: Option<SplitOptions>,
+    },
+    WeightedSplitCoins {
+        recipients: Vec<Recipient>,
+        options: Option<SplitOptions>,
     },
+    /// `recipient` receives the coins; defaults to the sender
     WithdrawCoins {
         amount: Option<Uint128>,
+        denom: Option<String>,
+        recipient: Option<String>,
+    },
+    /// Entry point for CW20 tokens sent with `Cw20ExecuteMsg::Send`; `msg` must be a `ReceiveMsg`
+    Receive(Cw20ReceiveMsg),
+    WithdrawCw20 {
+        token: String,
+        amount: Option<Uint128>,
+        recipient: Option<String>,
+    },
+    /// Owner only - pays out the accrued native fees, or the fees of one CW20 `token`, to
+    /// `recipient` (defaults to the fee collector). Each pool is withdrawn on its own so a
+    /// failing token transfer can't hold the others back.
+    WithdrawFees {
+        recipient: Option<String>,
+        token: Option<String>,
+    },
+    /// Owner only - sends whatever the contract holds of `denom` (default usei) beyond what it
+    /// owes, e.g. coins bank-sent to it directly, to `recipient` (defaults to the owner)
+    SweepSurplus {
+        recipient: Option<String>,
+        denom: Option<String>,
+    },
+    /// Owner only - see `UpdateConfigMsg`
+    UpdateConfig(UpdateConfigMsg),
+    /// Owner only - `owner` must call `AcceptOwnership` (before `expiry`) to take over
+    ProposeNewOwner {
+        owner: String,
+        expiry: Option<Expiration>,
+    },
+    CancelOwnershipTransfer {},
+    AcceptOwnership {},
+    /// Owner only - leaves the contract without an owner, permanently
+    RenounceOwnership {},
+    /// Owner only - sets or clears the guardian allowed to `Pause`
+    SetGuardian {
+        guardian: Option<String>,
+    },
+    /// Owner or guardian - pauses new splits and/or withdrawals; flags left false are unchanged
+    Pause {
+        splits: bool,
+        withdrawals: bool,
+    },
+    /// Owner only - resumes new splits and/or withdrawals; flags left false are unchanged
+    Unpause {
+        splits: bool,
+        withdrawals: bool,
+    },
+    /// Lets `spender` withdraw up to `amount` more of `denom` (default usei) from the sender's
+    /// balance. `expires` replaces the current expiry when set.
+    IncreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        denom: Option<String>,
+        expires: Option<Expiration>,
+    },
+    /// Lowers an allowance, removing it once it reaches zero
+    DecreaseAllowance {
+        spender: String,
+        amount: Uint128,
+        denom: Option<String>,
+        expires: Option<Expiration>,
+    },
+    /// Withdraws from `owner`'s balance against the sender's allowance; `amount` defaults to the
+    /// whole balance and `recipient` to the sender
+    WithdrawFrom {
+        owner: String,
+        amount: Option<Uint128>,
+        denom: Option<String>,
+        recipient: Option<String>,
+    },
+    /// Moves part of the sender's balance to another wallet in the contract, without fees
+    Transfer {
+        recipient: String,
+        amount: Uint128,
+        denom: Option<String>,
+    },
+    /// Moves part of `owner`'s balance to another wallet against the sender's allowance
+    TransferFrom {
+        owner: String,
+        recipient: String,
+        amount: Uint128,
+        denom: Option<String>,
+    },
+    /// Moves the sender's share of an expiring split into their wallet
+    Claim {
+        split_id: u64,
+    },
+    /// Returns the unclaimed shares of an expired split to its sender
+    Reclaim {
+        split_id: u64,
+    },
+    /// Owner or operator only - sends the full native balances of `addrs` to their owners, one
+    /// `BankMsg::Send` per account. Without `addrs`, pays the first `limit` funded wallets after
+    /// `start_after` instead; setting both is rejected. Vesting grants are not released.
+    BatchPayout {
+        addrs: Option<Vec<String>>,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// Saves `recipients` as a named group that anyone can split to with `SplitToGroup`
+    CreateSplitGroup {
+        name: String,
+        recipients: Vec<Recipient>,
+        mutable: bool,
+    },
+    /// Creator only, and only for mutable groups
+    UpdateSplitGroup {
+        group_id: u64,
+        name: Option<String>,
+        recipients: Option<Vec<Recipient>>,
+    },
+    /// Creator only
+    DeleteSplitGroup {
+        group_id: u64,
+    },
+    /// Splits the funds sent across a stored group by its weights
+    SplitToGroup {
+        group_id: u64,
+        options: Option<SplitOptions>,
+    },
+    /// Owner only - discounts the fees `addr` pays on its splits by `discount_bps`
+    /// (None or 10_000 waives them entirely)
+    AddFeeExemption {
+        addr: String,
+        discount_bps: Option<u16>,
+    },
+    /// Owner only
+    RemoveFeeExemption {
+        addr: String,
+    },
+}
+
+/// Fields left as None are not changed; an empty `allowed_denoms` accepts any denom
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct UpdateConfigMsg {
+    /// Shorthand for a `FeeSchedule::Percentage` without clamps - set at most one of the two
+    pub fee_bps: Option<u16>,
+    pub fee_schedule: Option<FeeSchedule>,
+    /// May only be lowered
+    pub max_fee_bps: Option<u16>,
+    pub fee_collector: Option<String>,
+    pub allowed_denoms: Option<Vec<String>>,
+    pub push_payouts: Option<bool>,
+    /// Replaces the list of operators allowed to run `BatchPayout`
+    pub operators: Option<Vec<String>>,
+    /// Replaces the CW20 tokens accepted besides `cw20_addr`
+    pub allowed_cw20s: Option<Vec<String>>,
+    pub remainder_policy: Option<RemainderPolicy>,
+    /// Replaces the list of minimum deposits
+    pub min_deposits: Option<Vec<Coin>>,
+    pub recipient_policy: Option<RecipientPolicy>,
+}
+
+/// Rules every split's recipients are checked against. The contract's own address is never
+/// accepted, since nothing credited to it could be withdrawn.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RecipientPolicy {
+    #[serde(default)]
+    pub duplicates: DuplicatePolicy,
+    /// Reject splits that pay the sender
+    #[serde(default)]
+    pub forbid_self: bool,
+}
+
+/// What to do with an address listed more than once in the same split
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Each listing is paid as a separate recipient
+    Allow,
+    /// Listings are combined into one recipient with the sum of their weights
+    Merge,
+    /// Splits that list an address twice fail with `DuplicateRecipient`
+    Reject,
+}
+
+impl Default for DuplicatePolicy {
+    fn default() -> Self {
+        DuplicatePolicy::Allow
+    }
+}
+
+/// Compact form used in response attributes, e.g. "duplicates=merge,forbid_self=true"
+impl fmt::Display for RecipientPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let duplicates = match self.duplicates {
+            DuplicatePolicy::Allow => "allow",
+            DuplicatePolicy::Merge => "merge",
+            DuplicatePolicy::Reject => "reject",
+        };
+        write!(
+            f,
+            "duplicates={},forbid_self={}",
+            duplicates, self.forbid_self
+        )
+    }
+}
+
+/// Where the units left over by a split that doesn't divide exactly end up. There are never
+/// more of them than recipients.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RemainderPolicy {
+    /// One unit each to the first recipients, in order
+    First,
+    /// All of it to the recipient with the largest weight, the first of them on a tie
+    LargestWeight,
+    /// One unit each to the next recipients in turn, continuing from where the last split left off
+    RoundRobin,
+    /// Added to the accrued fees
+    FeeCollector,
+    /// Held by the contract and added to the next split of the same denom with this policy
+    Bucket,
+}
+
+impl Default for RemainderPolicy {
+    fn default() -> Self {
+        RemainderPolicy::First
+    }
+}
+
+impl fmt::Display for RemainderPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            RemainderPolicy::First => "first",
+            RemainderPolicy::LargestWeight => "largest_weight",
+            RemainderPolicy::RoundRobin => "round_robin",
+            RemainderPolicy::FeeCollector => "fee_collector",
+            RemainderPolicy::Bucket => "bucket",
+        })
+    }
+}
+
+/// Per-split settings; fields left as None fall back to the contract config
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct SplitOptions {
+    /// Send each share to its recipient straight away instead of crediting their wallet.
+    /// Shares that cannot be delivered are credited to the wallet instead.
+    pub push: Option<bool>,
+    /// Credit each native share gradually on this schedule rather than all at once; vesting
+    /// shares are never pushed, and a recipient holds at most 20 unreleased grants
+    pub vesting: Option<Vesting>,
+    /// Hold the shares until each recipient claims theirs with `Claim`; once this passes the
+    /// sender can take back whatever is unclaimed with `Reclaim`
+    pub expires: Option<Expiration>,
+    /// Overrides the configured `RemainderPolicy`
+    pub remainder: Option<RemainderPolicy>,
+}
+
+/// Split instruction embedded in the `msg` of a `Cw20ReceiveMsg`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    SplitCoins {
+        target_addr1: String,
+        target_addr2: String,
+    },
+    WeightedSplitCoins {
+        recipients: Vec<Recipient>,
+    },
+    SplitToGroup {
+        group_id: u64,
     },
 }
 
@@ -25,23 +330,180 @@ pub enum ExecuteMsg {
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetOwner {},
-    GetWallet { addr: Addr },
-    GetFee {},
+    /// Returns PauseStatusResponse
+    PauseStatus {},
+    /// Compares what the contract owes in `denom` (default usei) with its bank balance
+    Solvency {
+        denom: Option<String>,
+    },
+    GetWallet {
+        addr: Addr,
+    },
+    /// Every wallet with a non-zero balance, in storage order
+    ListWallets {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+    /// `amount` simulates the fee a split of that coin would pay, after `sender`'s exemption.
+    /// For CW20 tokens, the denom is the token contract address.
+    GetFee {
+        amount: Option<Coin>,
+        sender: Option<String>,
+    },
+    /// Returns cw20::AllowanceResponse; `denom` defaults to usei
+    Allowance {
+        owner: String,
+        spender: String,
+        denom: Option<String>,
+    },
+    /// Every allowance granted by `owner`, paged by (spender, denom)
+    AllAllowances {
+        owner: String,
+        start_after: Option<(String, String)>,
+        limit: Option<u32>,
+    },
+    /// Returns SplitRecord
+    GetSplit {
+        split_id: u64,
+    },
+    ListSplitsBySender {
+        sender: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    ListSplitsByRecipient {
+        recipient: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns Escrow while any of its shares are still held; see `GetSplit` afterwards
+    GetEscrow {
+        split_id: u64,
+    },
+    ListEscrows {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Returns SplitGroup
+    GetSplitGroup {
+        group_id: u64,
+    },
+    ListSplitGroups {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+    /// Vesting grants of `addr` that are not fully released yet
+    GetVesting {
+        addr: String,
+    },
+    ListFeeExemptions {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PauseStatusResponse {
+    pub splits_paused: bool,
+    pub withdrawals_paused: bool,
+    pub guardian: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SolvencyResponse {
+    pub denom: String,
+    pub balance: Uint128,
+    pub totals: Totals,
+    /// Whether the balance covers everything owed
+    pub solvent: bool,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct OwnerResponse {
-    pub owner: Addr,
+    /// None once ownership has been renounced
+    pub owner: Option<Addr>,
+    pub pending_owner: Option<Addr>,
+    pub pending_expiry: Option<Expiration>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct WalletResponse {
     pub addr: Addr,
-    pub amount: Uint128,
+    pub balances: Vec<Coin>,
+    pub cw20_balances: Vec<Cw20CoinVerified>,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct FeeResponse {
+    pub fee_collector: Addr,
+    pub fee_schedule: FeeSchedule,
+    pub max_fee_bps: u16,
+    /// Fee for the amount given in `QueryMsg::GetFee`, if any
+    pub simulated_fee: Option<Coin>,
+    pub accrued: Vec<Coin>,
+    pub cw20_accrued: Vec<Cw20CoinVerified>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ListWalletsResponse {
+    pub wallets: Vec<WalletResponse>,
+    /// Pass as `start_after` to fetch the next page; None on the last page
+    pub next: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeExemption {
+    pub addr: Addr,
+    pub discount_bps: u16,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeExemptionsResponse {
+    pub exemptions: Vec<FeeExemption>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllowanceInfo {
+    pub spender: Addr,
+    pub denom: String,
+    pub allowance: Uint128,
+    pub expires: Expiration,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AllAllowancesResponse {
+    pub allowances: Vec<AllowanceInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SplitGroupsResponse {
+    pub groups: Vec<SplitGroup>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingGrantInfo {
+    pub id: u64,
+    pub denom: String,
+    pub total: Uint128,
+    pub released: Uint128,
+    /// Vested now but not yet moved into the wallet; released on the next withdrawal or transfer
+    /// of `denom`
+    pub vested: Uint128,
+    pub vesting: Vesting,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingResponse {
     pub addr: Addr,
-    pub fee_percent: Uint128,
+    pub grants: Vec<VestingGrantInfo>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowsResponse {
+    pub escrows: Vec<Escrow>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SplitsResponse {
+    pub splits: Vec<SplitRecord>,
 }
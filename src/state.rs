@@ -1,16 +1,205 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::{Item, Map};
+use cosmwasm_std::{Addr, Coin, Empty, Timestamp, Uint128};
+use cw20::{AllowanceResponse, Expiration};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, Map, MultiIndex};
+
+use crate::fees::FeeSchedule;
+use crate::msg::{Recipient, RecipientPolicy, RemainderPolicy};
+use crate::vesting::Vesting;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
-    pub owner: Addr,
+    /// None once ownership has been renounced
+    pub owner: Option<Addr>,
+    /// CW20 token accepted by `Receive`, alongside `allowed_cw20s`
     pub cw20_addr: Addr,
-    pub fee_percent: Uint128,
+    /// Further CW20 tokens accepted by `Receive`
+    #[serde(default)]
+    pub allowed_cw20s: Vec<Addr>,
+    pub fee_collector: Addr,
+    pub fee_schedule: FeeSchedule,
+    /// Cap on the fee of any split, in basis points of the split amount
+    pub max_fee_bps: u16,
+    /// None accepts every native denom
+    pub allowed_denoms: Option<Vec<String>>,
+    /// Whether native splits pay recipients immediately unless the split says otherwise
+    #[serde(default)]
+    pub push_payouts: bool,
+    /// Addresses besides the owner that may run `BatchPayout`
+    #[serde(default)]
+    pub operators: Vec<Addr>,
+    /// May pause the contract alongside the owner, but only the owner can unpause it
+    #[serde(default)]
+    pub guardian: Option<Addr>,
+    #[serde(default)]
+    pub remainder_policy: RemainderPolicy,
+    /// Smallest accepted split per denom, CW20 tokens keyed by contract address
+    #[serde(default)]
+    pub min_deposits: Vec<Coin>,
+    #[serde(default)]
+    pub recipient_policy: RecipientPolicy,
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
 
-pub const WALLETS: Map<Addr, Uint128> = Map::new("wallets");
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingOwner {
+    pub new_owner: Addr,
+    pub expiry: Option<Expiration>,
+}
+
+// ownership transfer proposed by the owner and awaiting AcceptOwnership
+pub const PENDING_OWNER: Item<PendingOwner> = Item::new("pending_owner");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct PauseState {
+    /// Blocks new deposits - every kind of split, native or CW20
+    pub splits: bool,
+    /// Blocks every message that sends funds out of the contract
+    pub withdrawals: bool,
+}
+
+pub const PAUSED: Item<PauseState> = Item::new("paused");
+
+// withdrawable balances keyed by (owner, denom)
+pub const WALLETS: Map<(Addr, String), Uint128> = Map::new("wallets");
+
+// amounts a spender may withdraw from an owner's native balance, keyed by (owner, spender, denom)
+pub const ALLOWANCES: Map<(Addr, Addr, String), AllowanceResponse> = Map::new("allowances");
+
+// CW20 balances keyed by (owner, token contract)
+pub const CW20_WALLETS: Map<(Addr, Addr), Uint128> = Map::new("cw20_wallets");
+
+// fees accrued by splits and not yet withdrawn, keyed by denom / token contract
+pub const FEES: Map<String, Uint128> = Map::new("fees");
+pub const CW20_FEES: Map<Addr, Uint128> = Map::new("cw20_fees");
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Totals {
+    /// Everything ever split, fees included
+    pub deposited: Uint128,
+    /// What the contract holds for others right now - wallets, grants, escrows, pending payouts
+    /// and fees not yet withdrawn. Never more than the contract's bank balance.
+    pub owed: Uint128,
+    pub fees_accrued: Uint128,
+    /// Everything ever paid out, fees included
+    pub withdrawn: Uint128,
+}
+
+// running totals of the native ledger keyed by denom; CW20 tokens are not tracked
+pub const TOTALS: Map<String, Totals> = Map::new("totals");
+
+// split remainders held under `RemainderPolicy::Bucket`, keyed by denom / token contract
+pub const REMAINDERS: Map<String, Uint128> = Map::new("remainders");
+pub const CW20_REMAINDERS: Map<Addr, Uint128> = Map::new("cw20_remainders");
+
+// next recipient index for `RemainderPolicy::RoundRobin`, shared by every split
+pub const ROUND_ROBIN_CURSOR: Item<u64> = Item::new("round_robin_cursor");
+
+// fee discount in basis points for exempt senders (10_000 = no fee at all)
+pub const FEE_EXEMPTIONS: Map<Addr, u16> = Map::new("fee_exemptions");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingPayout {
+    pub recipient: Addr,
+    pub amount: Vec<Coin>,
+}
+
+// push payouts in flight, keyed by the id of their submessage, until the reply settles them
+pub const PENDING_PAYOUTS: Map<u64, PendingPayout> = Map::new("pending_payouts");
+pub const PAYOUT_SEQ: Item<u64> = Item::new("payout_seq");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SplitGroup {
+    pub id: u64,
+    pub name: String,
+    pub creator: Addr,
+    /// Recipient addresses are validated when the group is saved
+    pub recipients: Vec<Recipient>,
+    /// Immutable groups can never be updated, only deleted
+    pub mutable: bool,
+}
+
+// reusable recipient lists for SplitToGroup, keyed by an incrementing id
+pub const SPLIT_GROUPS: Map<u64, SplitGroup> = Map::new("split_groups");
+pub const GROUP_SEQ: Item<u64> = Item::new("group_seq");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct VestingGrant {
+    pub denom: String,
+    pub total: Uint128,
+    /// Amount already moved into the recipient's wallet
+    pub released: Uint128,
+    pub vesting: Vesting,
+}
+
+// shares of vesting splits that have not been fully released, keyed by (recipient, grant id)
+pub const VESTING_GRANTS: Map<(Addr, u64), VestingGrant> = Map::new("vesting_grants");
+// number of grants each recipient holds in VESTING_GRANTS
+pub const VESTING_GRANT_COUNTS: Map<Addr, u32> = Map::new("vesting_grant_counts");
+pub const GRANT_SEQ: Item<u64> = Item::new("grant_seq");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Escrow {
+    pub id: u64,
+    pub sender: Addr,
+    /// Shares must be claimed before this; afterwards the sender may reclaim the rest
+    pub expires: Expiration,
+    pub shares: Vec<EscrowShare>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct EscrowShare {
+    pub recipient: Addr,
+    pub amount: Vec<Coin>,
+    pub claimed: bool,
+}
+
+// shares of expiring splits awaiting Claim or Reclaim, keyed by split id. An escrow is removed
+// once every share is claimed or the rest is reclaimed; splits() keeps the provenance.
+pub const ESCROWS: Map<u64, Escrow> = Map::new("escrows");
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SplitRecord {
+    pub id: u64,
+    pub sender: Addr,
+    /// CW20 token contract, None for native coins. CW20 amounts use the token address as denom.
+    pub token: Option<Addr>,
+    /// Amount split after fees
+    pub amount: Vec<Coin>,
+    pub fees: Vec<Coin>,
+    pub shares: Vec<Share>,
+    pub height: u64,
+    pub time: Timestamp,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Share {
+    pub recipient: Addr,
+    pub amount: Vec<Coin>,
+}
+
+pub struct SplitIndexes<'a> {
+    pub sender: MultiIndex<'a, Addr, SplitRecord, u64>,
+}
+
+impl<'a> IndexList<SplitRecord> for SplitIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<SplitRecord>> + '_> {
+        let v: Vec<&dyn Index<SplitRecord>> = vec![&self.sender];
+        Box::new(v.into_iter())
+    }
+}
+
+// every split ever made, keyed by split id and indexed by sender
+pub fn splits<'a>() -> IndexedMap<'a, u64, SplitRecord, SplitIndexes<'a>> {
+    let indexes = SplitIndexes {
+        sender: MultiIndex::new(|record| record.sender.clone(), "splits", "splits__sender"),
+    };
+    IndexedMap::new("splits", indexes)
+}
+
+// split ids by recipient - a split has several recipients, so this is kept by hand
+pub const SPLITS_BY_RECIPIENT: Map<(Addr, u64), Empty> = Map::new("splits__recipient");
+pub const SPLIT_SEQ: Item<u64> = Item::new("split_seq");
@@ -1,12 +1,26 @@
 #[cfg(test)]
 mod tests {
-    use crate::contract::{execute, instantiate, query};
+    use crate::contract::{execute, instantiate, migrate, query, reply};
     use crate::error::ContractError;
-    use crate::msg::{ExecuteMsg, InstantiateMsg, OwnerResponse, QueryMsg, WalletResponse};
-    use crate::state::{CONFIG, WALLETS};
+    use crate::fees::{FeeSchedule, FeeTier};
+    use crate::migrations::seed_totals;
+    use crate::msg::{
+        AllAllowancesResponse, AllowanceInfo, DuplicatePolicy, EscrowsResponse, ExecuteMsg,
+        FeeExemption, FeeExemptionsResponse, FeeResponse, InstantiateMsg, ListWalletsResponse,
+        MigrateMsg, OwnerResponse, PauseStatusResponse, QueryMsg, ReceiveMsg, Recipient,
+        RecipientPolicy, RemainderPolicy, SolvencyResponse, SplitGroupsResponse, SplitOptions,
+        SplitsResponse, UpdateConfigMsg, VestingResponse, WalletResponse,
+    };
+    use crate::state::{
+        splits, Escrow, SplitGroup, SplitRecord, Totals, CONFIG, CW20_FEES, CW20_WALLETS, FEES,
+        PENDING_PAYOUTS, REMAINDERS, TOTALS, WALLETS,
+    };
+    use crate::vesting::Vesting;
     use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
-    use cosmwasm_std::{coin, coins, BankMsg, Uint128};
-    use cosmwasm_std::{from_binary, Addr, CosmosMsg};
+    use cosmwasm_std::{coin, coins, to_binary, BankMsg, Uint128, WasmMsg};
+    use cosmwasm_std::{from_binary, Addr, CosmosMsg, Storage};
+    use cosmwasm_std::{Reply, SubMsg, SubMsgResponse, SubMsgResult};
+    use cw20::{AllowanceResponse, Cw20CoinVerified, Cw20ExecuteMsg, Cw20ReceiveMsg, Expiration};
 
     #[test]
     // Test when the InstantiateMsg.owner=None, should set owner as info.sender
@@ -15,13 +29,22 @@ mod tests {
         let msg = InstantiateMsg {
             owner: None,
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         let config = CONFIG.load(&deps.storage).unwrap();
-        assert_eq!(MOCK_CONTRACT_ADDR, config.owner.to_string());
+        assert_eq!(MOCK_CONTRACT_ADDR, config.owner.unwrap().to_string());
     }
 
     #[test]
@@ -31,13 +54,22 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
 
         let config = CONFIG.load(&deps.storage).unwrap();
-        assert_eq!(String::from("eric"), config.owner.to_string());
+        assert_eq!(String::from("eric"), config.owner.unwrap().to_string());
     }
 
     #[test]
@@ -47,24 +79,45 @@ mod tests {
         let msg = InstantiateMsg {
             owner: None,
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
         let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
 
         let owner: OwnerResponse = from_binary(&res).unwrap();
-        assert_eq!(String::from(MOCK_CONTRACT_ADDR), owner.owner.to_string())
+        assert_eq!(
+            String::from(MOCK_CONTRACT_ADDR),
+            owner.owner.unwrap().to_string()
+        )
     }
 
     #[test]
-    // Test calling ExecuteMsg::SplitCoins with tokens besides usei or multiple tokens - should throw error
+    // Test calling ExecuteMsg::SplitCoins without funds or with a zero amount coin - should throw error
     fn execute_split_coins_invalid_token_funds() {
         let mut deps = mock_dependencies();
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -72,19 +125,19 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
-        let info_missing_usei = mock_info(MOCK_CONTRACT_ADDR, &[coin(100, "abc")]);
-        let info_multiple_coins =
-            mock_info(MOCK_CONTRACT_ADDR, &[coin(100, "usei"), coin(10, "abc")]);
+        let info_missing_funds = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let info_zero_coin = mock_info(MOCK_CONTRACT_ADDR, &[coin(100, "usei"), coin(0, "abc")]);
 
-        let res = execute(deps.as_mut(), mock_env(), info_missing_usei, msg.clone());
+        let res = execute(deps.as_mut(), mock_env(), info_missing_funds, msg.clone());
         assert!(res.is_err());
         match res.unwrap_err() {
             ContractError::InvalidTokenTransfer {} => {}
             e => panic!("unexpected error: {:?}", e),
         }
 
-        let res = execute(deps.as_mut(), mock_env(), info_multiple_coins, msg.clone());
+        let res = execute(deps.as_mut(), mock_env(), info_zero_coin, msg.clone());
         assert!(res.is_err());
         match res.unwrap_err() {
             ContractError::InvalidTokenTransfer {} => {}
@@ -99,7 +152,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -108,14 +170,21 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
 
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         let balance2 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test2"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), String::from("usei")),
+            )
             .unwrap();
 
         assert_eq!(0, res.messages.len());
@@ -130,7 +199,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -139,14 +217,21 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
 
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         let balance2 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test2"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), String::from("usei")),
+            )
             .unwrap();
 
         assert_eq!(0, res.messages.len());
@@ -162,7 +247,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -171,6 +265,7 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -178,6 +273,7 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test3"),
+            options: None,
         };
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -185,17 +281,27 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test2"),
             target_addr2: String::from("test1"),
+            options: None,
         };
         execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         let balance2 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test2"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), String::from("usei")),
+            )
             .unwrap();
         let balance3 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test3"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test3"), String::from("usei")),
+            )
             .unwrap();
 
         assert_eq!(Uint128::new(51 + 25 + 2), balance1);
@@ -210,14 +316,23 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert!(WALLETS
             .save(
                 deps.as_mut().storage,
-                Addr::unchecked("test1"),
+                (Addr::unchecked("test1"), String::from("usei")),
                 &Uint128::MAX
             )
             .is_ok());
@@ -225,6 +340,7 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, msg);
 
@@ -235,7 +351,10 @@ mod tests {
         }
 
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         assert_eq!(Uint128::MAX, balance1);
     }
@@ -247,7 +366,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -255,6 +383,7 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from(" "),
             target_addr2: String::from("test2"),
+            options: None,
         };
         let res = execute(deps.as_mut(), mock_env(), info, msg);
 
@@ -272,7 +401,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: Some(Uint128::new(2)),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(200),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -281,23 +419,28 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
 
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         let balance2 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test2"))
-            .unwrap();
-        let balance_owner = WALLETS
-            .load(&deps.storage, Addr::unchecked(MOCK_CONTRACT_ADDR))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), String::from("usei")),
+            )
             .unwrap();
+        let fees = FEES.load(&deps.storage, String::from("usei")).unwrap();
 
         assert_eq!(0, res.messages.len());
         assert_eq!(Uint128::new(49), balance1);
         assert_eq!(Uint128::new(49), balance2);
-        assert_eq!(Uint128::new(2), balance_owner);
+        assert_eq!(Uint128::new(2), fees);
     }
 
     #[test]
@@ -307,7 +450,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: Some(Uint128::new(11)),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_100),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -316,23 +468,28 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
 
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         let balance2 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test2"))
-            .unwrap();
-        let balance_owner = WALLETS
-            .load(&deps.storage, Addr::unchecked(MOCK_CONTRACT_ADDR))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), String::from("usei")),
+            )
             .unwrap();
+        let fees = FEES.load(&deps.storage, String::from("usei")).unwrap();
 
         assert_eq!(0, res.messages.len());
         assert_eq!(Uint128::new(5), balance1);
         assert_eq!(Uint128::new(4), balance2);
-        assert_eq!(Uint128::new(1), balance_owner);
+        assert_eq!(Uint128::new(1), fees);
     }
 
     #[test]
@@ -342,7 +499,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from("eric"),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info("eric", &[]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -350,13 +516,19 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[coin(100, "usei")]);
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert_eq!(0, res.messages.len());
 
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+
         let msg = ExecuteMsg::WithdrawCoins {
             amount: Some(Uint128::new(50)),
+            denom: None,
+            recipient: None,
         };
         let info = mock_info("test1", &[]);
 
@@ -371,14 +543,21 @@ mod tests {
             })
         );
 
+        // an emptied wallet is removed rather than left at zero
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         let balance2 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test2"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), String::from("usei")),
+            )
             .unwrap();
 
-        assert_eq!(balance1, Uint128::new(0));
+        assert_eq!(balance1, None);
         assert_eq!(balance2, Uint128::new(50));
     }
 
@@ -389,7 +568,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from("eric"),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info("eric", &[]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -397,12 +585,20 @@ mod tests {
         let msg = ExecuteMsg::SplitCoins {
             target_addr1: String::from("test1"),
             target_addr2: String::from("test2"),
+            options: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[coin(100, "usei")]);
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert_eq!(0, res.messages.len());
 
-        let msg = ExecuteMsg::WithdrawCoins { amount: None };
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+
+        let msg = ExecuteMsg::WithdrawCoins {
+            amount: None,
+            denom: None,
+            recipient: None,
+        };
         let info = mock_info("test1", &[]);
 
         let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -416,14 +612,21 @@ mod tests {
             })
         );
 
+        // an emptied wallet is removed rather than left at zero
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         let balance2 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test2"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), String::from("usei")),
+            )
             .unwrap();
 
-        assert_eq!(balance1, Uint128::new(0));
+        assert_eq!(balance1, None);
         assert_eq!(balance2, Uint128::new(50));
     }
 
@@ -434,20 +637,31 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
         assert!(WALLETS
             .save(
                 deps.as_mut().storage,
-                Addr::unchecked("test1"),
+                (Addr::unchecked("test1"), String::from("usei")),
                 &Uint128::new(49)
             )
             .is_ok());
 
         let msg = ExecuteMsg::WithdrawCoins {
             amount: Some(Uint128::new(50)),
+            denom: None,
+            recipient: None,
         };
         let info = mock_info("test1", &[]);
 
@@ -467,13 +681,24 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
         instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
 
         let msg = ExecuteMsg::WithdrawCoins {
             amount: Some(Uint128::new(50)),
+            denom: None,
+            recipient: None,
         };
         let info = mock_info("test1", &[]);
 
@@ -494,20 +719,36 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from("eric"),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info("eric", &[]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
         assert!(WALLETS
             .save(
                 deps.as_mut().storage,
-                Addr::unchecked("test1"),
+                (Addr::unchecked("test1"), String::from("usei")),
                 &Uint128::new(50)
             )
             .is_ok());
 
+        // wallets written directly bypass the running totals
+        seed_totals(&mut deps.storage).unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(50, "usei"));
+
         let msg = ExecuteMsg::WithdrawCoins {
             amount: Some(Uint128::new(20)),
+            denom: None,
+            recipient: None,
         };
         let info = mock_info("test1", &[]);
 
@@ -522,12 +763,17 @@ mod tests {
         );
 
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         assert_eq!(balance1, Uint128::new(30));
 
         let msg = ExecuteMsg::WithdrawCoins {
             amount: Some(Uint128::new(25)),
+            denom: None,
+            recipient: None,
         };
         let info = mock_info("test1", &[]);
 
@@ -542,7 +788,10 @@ mod tests {
         );
 
         let balance1 = WALLETS
-            .load(&deps.storage, Addr::unchecked("test1"))
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
             .unwrap();
         assert_eq!(balance1, Uint128::new(5));
     }
@@ -554,7 +803,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[coin(100, "abc"), coin(99, "usei")]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -570,7 +828,7 @@ mod tests {
 
         let wallet: WalletResponse = from_binary(&res).unwrap();
         assert_eq!("test1", wallet.addr.to_string());
-        assert_eq!(Uint128::new(0), wallet.amount);
+        assert!(wallet.balances.is_empty());
     }
 
     #[test]
@@ -580,7 +838,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[coin(100, "abc"), coin(99, "usei")]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -588,7 +855,7 @@ mod tests {
         assert!(WALLETS
             .save(
                 deps.as_mut().storage,
-                Addr::unchecked("test1"),
+                (Addr::unchecked("test1"), String::from("usei")),
                 &Uint128::new(0)
             )
             .is_ok());
@@ -604,7 +871,7 @@ mod tests {
 
         let wallet: WalletResponse = from_binary(&res).unwrap();
         assert_eq!("test1", wallet.addr.to_string());
-        assert_eq!(Uint128::new(0), wallet.amount);
+        assert!(wallet.balances.is_empty());
     }
 
     #[test]
@@ -614,7 +881,16 @@ mod tests {
         let msg = InstantiateMsg {
             owner: Some(String::from("eric")),
             cw20_addr: String::from(MOCK_CONTRACT_ADDR),
-            fee_percent: None,
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
         };
         let info = mock_info(MOCK_CONTRACT_ADDR, &[coin(100, "abc"), coin(99, "usei")]);
         instantiate(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -622,7 +898,7 @@ mod tests {
         assert!(WALLETS
             .save(
                 deps.as_mut().storage,
-                Addr::unchecked("test1"),
+                (Addr::unchecked("test1"), String::from("usei")),
                 &Uint128::new(100)
             )
             .is_ok());
@@ -638,6 +914,4507 @@ mod tests {
 
         let wallet: WalletResponse = from_binary(&res).unwrap();
         assert_eq!("test1", wallet.addr.to_string());
-        assert_eq!(Uint128::new(100), wallet.amount);
+        assert_eq!(coins(100, "usei"), wallet.balances);
+    }
+
+    #[test]
+    // Test ExecuteMsg::WeightedSplitCoins - shares are proportional to weight, rounding remainder goes to the first recipients
+    fn execute_weighted_split_coins_proportional() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &[coin(100, "usei")]);
+        let msg = ExecuteMsg::WeightedSplitCoins {
+            recipients: vec![
+                Recipient {
+                    addr: String::from("test1"),
+                    weight: 1,
+                },
+                Recipient {
+                    addr: String::from("test2"),
+                    weight: 2,
+                },
+                Recipient {
+                    addr: String::from("test3"),
+                    weight: 3,
+                },
+            ],
+            options: None,
+        };
+
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let balance1 = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
+            .unwrap();
+        let balance2 = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), String::from("usei")),
+            )
+            .unwrap();
+        let balance3 = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test3"), String::from("usei")),
+            )
+            .unwrap();
+
+        assert_eq!(0, res.messages.len());
+        assert_eq!(Uint128::new(16 + 1), balance1);
+        assert_eq!(Uint128::new(33), balance2);
+        assert_eq!(Uint128::new(50), balance3);
+    }
+
+    #[test]
+    // Test ExecuteMsg::WeightedSplitCoins with equal weights - remainder is handed out one unit at a time in order
+    fn execute_weighted_split_coins_remainder_distribution() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let recipients: Vec<Recipient> = (1..=5)
+            .map(|i| Recipient {
+                addr: format!("test{}", i),
+                weight: 1,
+            })
+            .collect();
+        let info = mock_info("sender", &[coin(103, "usei")]);
+        let msg = ExecuteMsg::WeightedSplitCoins {
+            recipients,
+            options: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let balances: Vec<Uint128> = (1..=5)
+            .map(|i| {
+                WALLETS
+                    .load(
+                        &deps.storage,
+                        (Addr::unchecked(format!("test{}", i)), String::from("usei")),
+                    )
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(
+            vec![
+                Uint128::new(21),
+                Uint128::new(21),
+                Uint128::new(21),
+                Uint128::new(20),
+                Uint128::new(20)
+            ],
+            balances
+        );
+    }
+
+    #[test]
+    // Test ExecuteMsg::WeightedSplitCoins with no recipients or a zero weight - should throw error
+    fn execute_weighted_split_coins_invalid_recipients() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &[coin(100, "usei")]);
+        let msg = ExecuteMsg::WeightedSplitCoins {
+            recipients: vec![],
+            options: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info.clone(), msg);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::WeightedSplitCoins {
+            recipients: vec![
+                Recipient {
+                    addr: String::from("test1"),
+                    weight: 1,
+                },
+                Recipient {
+                    addr: String::from("test2"),
+                    weight: 0,
+                },
+            ],
+            options: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        assert!(WALLETS
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    // Test calling ExecuteMsg::SplitCoins with several native denoms - each denom is split independently
+    fn execute_split_coins_multiple_denoms() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &[coin(100, "usei"), coin(21, "ibc/atom")]);
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("test1"),
+            target_addr2: String::from("test2"),
+            options: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetWallet {
+                addr: Addr::unchecked("test1"),
+            },
+        )
+        .unwrap();
+        let wallet: WalletResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            vec![coin(10, "ibc/atom"), coin(45, "usei")],
+            wallet.balances
+        );
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetWallet {
+                addr: Addr::unchecked("test2"),
+            },
+        )
+        .unwrap();
+        let wallet: WalletResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![coin(9, "ibc/atom"), coin(45, "usei")], wallet.balances);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetFee {
+                amount: None,
+                sender: None,
+            },
+        )
+        .unwrap();
+        let fee: FeeResponse = from_binary(&res).unwrap();
+        assert_eq!(vec![coin(2, "ibc/atom"), coin(10, "usei")], fee.accrued);
+    }
+
+    #[test]
+    // Test ExecuteMsg::WithdrawCoins with a denom - only that denom's balance is withdrawn
+    fn execute_withdraw_coins_specific_denom() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("sender", &[coin(100, "usei"), coin(40, "ibc/atom")]);
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("test1"),
+            target_addr2: String::from("test2"),
+            options: None,
+        };
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        deps.querier.update_balance(
+            MOCK_CONTRACT_ADDR,
+            vec![coin(100, "usei"), coin(40, "ibc/atom")],
+        );
+
+        let msg = ExecuteMsg::WithdrawCoins {
+            amount: None,
+            denom: Some(String::from("ibc/atom")),
+            recipient: None,
+        };
+        let info = mock_info("test1", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].clone().msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("test1"),
+                amount: coins(20u128, "ibc/atom")
+            })
+        );
+
+        let atom_balance = WALLETS
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("ibc/atom")),
+            )
+            .unwrap();
+        let usei_balance = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(None, atom_balance);
+        assert_eq!(Uint128::new(50), usei_balance);
+
+        // withdrawing a denom that was never credited should fail
+        let msg = ExecuteMsg::WithdrawCoins {
+            amount: None,
+            denom: Some(String::from("uosmo")),
+            recipient: None,
+        };
+        let info = mock_info("test1", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::InsufficientFunds {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test ExecuteMsg::Receive with an embedded SplitCoins - CW20 balances are tracked per token
+    fn execute_receive_cw20_split_coins() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(200),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("sender"),
+            amount: Uint128::new(101),
+            msg: to_binary(&ReceiveMsg::SplitCoins {
+                target_addr1: String::from("test1"),
+                target_addr2: String::from("test2"),
+            })
+            .unwrap(),
+        });
+        let info = mock_info("token", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(0, res.messages.len());
+
+        let balance1 = CW20_WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), Addr::unchecked("token")),
+            )
+            .unwrap();
+        let balance2 = CW20_WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test2"), Addr::unchecked("token")),
+            )
+            .unwrap();
+        assert_eq!(Uint128::new(50), balance1);
+        assert_eq!(Uint128::new(49), balance2);
+        assert_eq!(
+            Uint128::new(2),
+            CW20_FEES
+                .load(&deps.storage, Addr::unchecked("token"))
+                .unwrap()
+        );
+
+        // CW20 balances never leak into the native ledger
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetWallet {
+                addr: Addr::unchecked("test1"),
+            },
+        )
+        .unwrap();
+        let wallet: WalletResponse = from_binary(&res).unwrap();
+        assert!(wallet.balances.is_empty());
+        assert_eq!(
+            vec![Cw20CoinVerified {
+                address: Addr::unchecked("token"),
+                amount: Uint128::new(50)
+            }],
+            wallet.cw20_balances
+        );
+    }
+
+    #[test]
+    // Test ExecuteMsg::Receive from a token that is not listed in the config - should throw error
+    fn execute_receive_cw20_unlisted_token() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("sender"),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::SplitCoins {
+                target_addr1: String::from("test1"),
+                target_addr2: String::from("test2"),
+            })
+            .unwrap(),
+        });
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("other_token", &[]),
+            msg.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::TokenNotAllowed { token } => assert_eq!(token, "other_token"),
+            e => panic!("unexpected error: {:?}", e),
+        }
+        assert!(CW20_WALLETS
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("test1"), Addr::unchecked("other_token"))
+            )
+            .unwrap()
+            .is_none());
+
+        // the owner can accept further tokens
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            allowed_cw20s: Some(vec![String::from("other_token")]),
+            ..Default::default()
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), update).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("other_token", &[]),
+            msg,
+        )
+        .unwrap();
+        let balance = CW20_WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), Addr::unchecked("other_token")),
+            )
+            .unwrap();
+        assert_eq!(balance, Uint128::new(50));
+
+        // or list them from the start
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: Some(vec![String::from("other_token")]),
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("sender"),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::SplitCoins {
+                target_addr1: String::from("test1"),
+                target_addr2: String::from("test2"),
+            })
+            .unwrap(),
+        });
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("other_token", &[]),
+            msg,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    // Test ExecuteMsg::Receive with a msg that is not a ReceiveMsg - should throw error
+    fn execute_receive_cw20_invalid_msg() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("sender"),
+            amount: Uint128::new(100),
+            msg: to_binary(&QueryMsg::GetFee {
+                amount: None,
+                sender: None,
+            })
+            .unwrap(),
+        });
+        let info = mock_info("token", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::Std(_) => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test ExecuteMsg::WithdrawCw20 - should emit a Cw20ExecuteMsg::Transfer to the token contract
+    fn execute_withdraw_cw20() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert!(CW20_WALLETS
+            .save(
+                deps.as_mut().storage,
+                (Addr::unchecked("test1"), Addr::unchecked("token")),
+                &Uint128::new(50)
+            )
+            .is_ok());
+
+        let msg = ExecuteMsg::WithdrawCw20 {
+            token: String::from("token"),
+            amount: Some(Uint128::new(20)),
+            recipient: None,
+        };
+        let info = mock_info("test1", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages[0].clone().msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("test1"),
+                    amount: Uint128::new(20),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        let balance1 = CW20_WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), Addr::unchecked("token")),
+            )
+            .unwrap();
+        assert_eq!(Uint128::new(30), balance1);
+
+        // a token the caller never received cannot be withdrawn
+        let msg = ExecuteMsg::WithdrawCw20 {
+            token: String::from("other_token"),
+            amount: None,
+            recipient: None,
+        };
+        let info = mock_info("test1", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::InsufficientFunds {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test QueryMsg::GetFee reports the fee collector - defaults to the owner when not set
+    fn query_fee_collector() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(200),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetFee {
+                amount: None,
+                sender: None,
+            },
+        )
+        .unwrap();
+        let fee: FeeResponse = from_binary(&res).unwrap();
+        assert_eq!(Addr::unchecked("eric"), fee.fee_collector);
+        assert_eq!(
+            FeeSchedule::Percentage {
+                bps: 200,
+                min: None,
+                max: None
+            },
+            fee.fee_schedule
+        );
+        assert_eq!(2_000, fee.max_fee_bps);
+        assert!(fee.accrued.is_empty());
+
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: Some(String::from("collector")),
+            fee_bps: Some(200),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetFee {
+                amount: None,
+                sender: None,
+            },
+        )
+        .unwrap();
+        let fee: FeeResponse = from_binary(&res).unwrap();
+        assert_eq!(Addr::unchecked("collector"), fee.fee_collector);
+    }
+
+    #[test]
+    // Test ExecuteMsg::WithdrawFees - native and CW20 fees are paid to the fee collector, or a chosen recipient
+    fn execute_withdraw_fees() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: Some(String::from("collector")),
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("test1"),
+            target_addr2: String::from("test2"),
+            options: None,
+        };
+        let info = mock_info("sender", &[coin(100, "usei")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("sender"),
+            amount: Uint128::new(50),
+            msg: to_binary(&ReceiveMsg::SplitCoins {
+                target_addr1: String::from("test1"),
+                target_addr2: String::from("test2"),
+            })
+            .unwrap(),
+        });
+        let info = mock_info("token", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+
+        let msg = ExecuteMsg::WithdrawFees {
+            recipient: None,
+            token: None,
+        };
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // native fees are paid on their own, the CW20 pool is left for its own call
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].clone().msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("collector"),
+                amount: coins(10u128, "usei")
+            })
+        );
+        let msg = ExecuteMsg::WithdrawFees {
+            recipient: None,
+            token: Some(String::from("token")),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("eric", &[]),
+            msg.clone(),
+        )
+        .unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].clone().msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("collector"),
+                    amount: Uint128::new(5),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+        assert!(FEES
+            .may_load(&deps.storage, String::from("usei"))
+            .unwrap()
+            .is_none());
+        let res = execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::InsufficientFunds {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // fees accrued afterwards can be routed to another recipient
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("test1"),
+            target_addr2: String::from("test2"),
+            options: None,
+        };
+        let info = mock_info("sender", &[coin(30, "usei")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(120, "usei"));
+
+        let msg = ExecuteMsg::WithdrawFees {
+            recipient: Some(String::from("treasury")),
+            token: None,
+        };
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(1, res.messages.len());
+        assert_eq!(
+            res.messages[0].clone().msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("treasury"),
+                amount: coins(3u128, "usei")
+            })
+        );
+    }
+
+    #[test]
+    // Test ExecuteMsg::WithdrawFees from a non-owner or with nothing accrued - should throw error
+    fn execute_withdraw_fees_invalid() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: Some(String::from("collector")),
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::WithdrawFees {
+            recipient: None,
+            token: None,
+        };
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::InsufficientFunds {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let split = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("test1"),
+            target_addr2: String::from("test2"),
+            options: None,
+        };
+        let info = mock_info("sender", &[coin(100, "usei")]);
+        execute(deps.as_mut(), mock_env(), info, split).unwrap();
+
+        // not even the fee collector may withdraw - only the owner
+        let info = mock_info("collector", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        assert_eq!(
+            Uint128::new(10),
+            FEES.load(&deps.storage, String::from("usei")).unwrap()
+        );
+    }
+
+    #[test]
+    // Test ExecuteMsg::UpdateConfig by the owner - only the fields that are set change
+    fn execute_update_config() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(200),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            fee_bps: Some(500),
+            fee_collector: Some(String::from("collector")),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            FeeSchedule::Percentage {
+                bps: 500,
+                min: None,
+                max: None
+            },
+            config.fee_schedule
+        );
+        assert_eq!(Addr::unchecked("collector"), config.fee_collector);
+        assert_eq!(None, config.allowed_denoms);
+
+        // fee_bps is still bounded by the default 20% cap
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            fee_bps: Some(2_001),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::FeeTooHigh {
+                fee_bps: 2_001,
+                max_fee_bps: 2_000,
+            } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test ExecuteMsg::UpdateConfig from a non-owner - should throw error
+    fn execute_update_config_unauthorized() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(200),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            fee_bps: Some(1_000),
+            ..Default::default()
+        });
+        let info = mock_info("mallory", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            FeeSchedule::Percentage {
+                bps: 200,
+                min: None,
+                max: None
+            },
+            config.fee_schedule
+        );
+    }
+
+    #[test]
+    // Test ExecuteMsg::SplitCoins with a denom outside config.allowed_denoms - should throw error
+    fn execute_split_coins_denom_not_allowed() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: Some(vec![String::from("usei")]),
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("test1"),
+            target_addr2: String::from("test2"),
+            options: None,
+        };
+        let info = mock_info("sender", &[coin(100, "usei"), coin(10, "abc")]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg.clone());
+        match res.unwrap_err() {
+            ContractError::DenomNotAllowed { denom } => assert_eq!("abc", denom),
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // an empty list re-opens the contract to every denom
+        let update = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            allowed_denoms: Some(vec![]),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        execute(deps.as_mut(), mock_env(), info, update).unwrap();
+
+        let info = mock_info("sender", &[coin(100, "usei"), coin(10, "abc")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    }
+
+    #[test]
+    // Test two-step ownership transfer - only the proposed owner can accept, then the old owner loses access
+    fn execute_ownership_transfer() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::ProposeNewOwner {
+            owner: String::from("alice"),
+            expiry: None,
+        };
+        let info = mock_info("eric", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+        let owner: OwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(Some(Addr::unchecked("eric")), owner.owner);
+        assert_eq!(Some(Addr::unchecked("alice")), owner.pending_owner);
+
+        let info = mock_info("mallory", &[]);
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let info = mock_info("alice", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+        let owner: OwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(Some(Addr::unchecked("alice")), owner.owner);
+        assert_eq!(None, owner.pending_owner);
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            fee_bps: Some(100),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test accepting an ownership proposal after its expiry - should throw error
+    fn execute_ownership_transfer_expired() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let env = mock_env();
+        let msg = ExecuteMsg::ProposeNewOwner {
+            owner: String::from("alice"),
+            expiry: Some(Expiration::AtHeight(env.block.height + 10)),
+        };
+        let info = mock_info("eric", &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        let mut late_env = env;
+        late_env.block.height += 10;
+        let info = mock_info("alice", &[]);
+        let res = execute(
+            deps.as_mut(),
+            late_env,
+            info,
+            ExecuteMsg::AcceptOwnership {},
+        );
+        match res.unwrap_err() {
+            ContractError::OwnershipProposalExpired {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(Some(Addr::unchecked("eric")), config.owner);
+    }
+
+    #[test]
+    // Test ExecuteMsg::RenounceOwnership - owner becomes None and owner-gated messages are rejected
+    fn execute_renounce_ownership() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let info = mock_info("eric", &[]);
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            info,
+            ExecuteMsg::RenounceOwnership {},
+        )
+        .unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::GetOwner {}).unwrap();
+        let owner: OwnerResponse = from_binary(&res).unwrap();
+        assert_eq!(None, owner.owner);
+
+        let msg = ExecuteMsg::ProposeNewOwner {
+            owner: String::from("eric"),
+            expiry: None,
+        };
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test ExecuteMsg::SplitCoins with a sub-percent fee in basis points - fee rounds down
+    fn execute_split_coins_with_bps_fee() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(25),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // 0.25% of 10_000 is exactly 25
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("test1"),
+            target_addr2: String::from("test2"),
+            options: None,
+        };
+        let info = mock_info("sender", &[coin(10_000, "usei")]);
+        execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        assert_eq!(
+            Uint128::new(25),
+            FEES.load(&deps.storage, String::from("usei")).unwrap()
+        );
+
+        // 0.25% of 399 is 0.9975 - rounded down to nothing
+        let info = mock_info("sender", &[coin(399, "usei")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            Uint128::new(25),
+            FEES.load(&deps.storage, String::from("usei")).unwrap()
+        );
+        let balance1 = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("test1"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(Uint128::new(4_988 + 200), balance1);
+    }
+
+    #[test]
+    // Test instantiating with a fee above the max fee cap, or a cap above 100% - should throw error
+    fn instantiate_fee_above_cap() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(600),
+            fee_schedule: None,
+            max_fee_bps: Some(500),
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::FeeTooHigh {
+                fee_bps: 600,
+                max_fee_bps: 500,
+            } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: Some(10_001),
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let res = instantiate(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test ExecuteMsg::UpdateConfig max_fee_bps - the owner can lower the cap but never raise it
+    fn execute_update_config_max_fee() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(300),
+            fee_schedule: None,
+            max_fee_bps: Some(1_000),
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            max_fee_bps: Some(1_001),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // lowering the cap below the current fee must come with a fee that fits
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            max_fee_bps: Some(200),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::FeeTooHigh { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            fee_bps: Some(150),
+            max_fee_bps: Some(200),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(
+            FeeSchedule::Percentage {
+                bps: 150,
+                min: None,
+                max: None
+            },
+            config.fee_schedule
+        );
+        assert_eq!(200, config.max_fee_bps);
+    }
+
+    #[test]
+    // Test a flat fee schedule - the same fee per split, but never more than max_fee_bps of the split
+    fn execute_split_coins_with_flat_fee() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: Some(FeeSchedule::Flat {
+                amount: coin(10, "usei"),
+            }),
+            max_fee_bps: Some(1_000),
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("test1"),
+            target_addr2: String::from("test2"),
+            options: None,
+        };
+        let info = mock_info("sender", &[coin(1_000, "usei")]);
+        execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        assert_eq!(
+            Uint128::new(10),
+            FEES.load(&deps.storage, String::from("usei")).unwrap()
+        );
+
+        // 10% of 50 is 5, so the flat fee of 10 is capped
+        let info = mock_info("sender", &[coin(50, "usei")]);
+        execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap();
+        assert_eq!(
+            Uint128::new(10 + 5),
+            FEES.load(&deps.storage, String::from("usei")).unwrap()
+        );
+
+        // the fee is in usei, so other coins of the same split pay nothing
+        let info = mock_info("sender", &[coin(1_000, "uatom"), coin(1_000, "usei")]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            Uint128::new(10 + 5 + 10),
+            FEES.load(&deps.storage, String::from("usei")).unwrap()
+        );
+        assert_eq!(
+            None,
+            FEES.may_load(&deps.storage, String::from("uatom")).unwrap()
+        );
+    }
+
+    #[test]
+    // Test QueryMsg::GetFee simulation for percentage fees with min/max clamps and for volume tiers
+    fn query_fee_simulation() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: Some(FeeSchedule::Percentage {
+                bps: 100,
+                min: Some(coin(5, "usei")),
+                max: Some(coin(50, "usei")),
+            }),
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let simulate = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, amount: u128, denom: &str| {
+            let res = query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::GetFee {
+                    amount: Some(coin(amount, denom)),
+                    sender: None,
+                },
+            )
+            .unwrap();
+            let fee: FeeResponse = from_binary(&res).unwrap();
+            fee.simulated_fee.unwrap()
+        };
+        assert_eq!(coin(5, "usei"), simulate(&deps, 100, "usei"));
+        assert_eq!(coin(20, "usei"), simulate(&deps, 2_000, "usei"));
+        assert_eq!(coin(50, "usei"), simulate(&deps, 1_000_000, "usei"));
+        // the clamps are in usei and leave other denoms at the plain percentage
+        assert_eq!(coin(1, "uatom"), simulate(&deps, 100, "uatom"));
+        assert_eq!(coin(10_000, "uatom"), simulate(&deps, 1_000_000, "uatom"));
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            fee_schedule: Some(FeeSchedule::Tiered {
+                denom: String::from("usei"),
+                tiers: vec![
+                    FeeTier {
+                        threshold: Uint128::zero(),
+                        bps: 300,
+                    },
+                    FeeTier {
+                        threshold: Uint128::new(10_000),
+                        bps: 200,
+                    },
+                    FeeTier {
+                        threshold: Uint128::new(1_000_000),
+                        bps: 50,
+                    },
+                ],
+            }),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        assert_eq!(coin(299, "usei"), simulate(&deps, 9_999, "usei"));
+        assert_eq!(coin(200, "usei"), simulate(&deps, 10_000, "usei"));
+        assert_eq!(coin(10_000, "usei"), simulate(&deps, 2_000_000, "usei"));
+        // thresholds are in usei, so other denoms stay at the first tier
+        assert_eq!(coin(60_000, "uatom"), simulate(&deps, 2_000_000, "uatom"));
+    }
+
+    #[test]
+    // Test invalid fee schedules - should throw error
+    fn execute_update_config_invalid_fee_schedule() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(100),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let invalid = vec![
+            // fee_bps and fee_schedule are mutually exclusive
+            UpdateConfigMsg {
+                fee_bps: Some(100),
+                fee_schedule: Some(FeeSchedule::Flat {
+                    amount: coin(1, "usei"),
+                }),
+                ..Default::default()
+            },
+            // min above max
+            UpdateConfigMsg {
+                fee_schedule: Some(FeeSchedule::Percentage {
+                    bps: 100,
+                    min: Some(coin(10, "usei")),
+                    max: Some(coin(5, "usei")),
+                }),
+                ..Default::default()
+            },
+            // fixed amounts need a denom
+            UpdateConfigMsg {
+                fee_schedule: Some(FeeSchedule::Flat {
+                    amount: coin(1, ""),
+                }),
+                ..Default::default()
+            },
+            // tiers must start at 0
+            UpdateConfigMsg {
+                fee_schedule: Some(FeeSchedule::Tiered {
+                    denom: String::from("usei"),
+                    tiers: vec![FeeTier {
+                        threshold: Uint128::new(10),
+                        bps: 100,
+                    }],
+                }),
+                ..Default::default()
+            },
+            // tiers must be sorted by threshold
+            UpdateConfigMsg {
+                fee_schedule: Some(FeeSchedule::Tiered {
+                    denom: String::from("usei"),
+                    tiers: vec![
+                        FeeTier {
+                            threshold: Uint128::zero(),
+                            bps: 100,
+                        },
+                        FeeTier {
+                            threshold: Uint128::new(100),
+                            bps: 50,
+                        },
+                        FeeTier {
+                            threshold: Uint128::new(100),
+                            bps: 10,
+                        },
+                    ],
+                }),
+                ..Default::default()
+            },
+        ];
+        for msg in invalid {
+            let info = mock_info("eric", &[]);
+            let res = execute(
+                deps.as_mut(),
+                mock_env(),
+                info,
+                ExecuteMsg::UpdateConfig(msg),
+            );
+            match res.unwrap_err() {
+                ContractError::InvalidParams {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+
+        // every tier is held to the cap
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            fee_schedule: Some(FeeSchedule::Tiered {
+                denom: String::from("usei"),
+                tiers: vec![
+                    FeeTier {
+                        threshold: Uint128::zero(),
+                        bps: 100,
+                    },
+                    FeeTier {
+                        threshold: Uint128::new(100),
+                        bps: 2_500,
+                    },
+                ],
+            }),
+            ..Default::default()
+        });
+        let info = mock_info("eric", &[]);
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::FeeTooHigh {
+                fee_bps: 2_500,
+                max_fee_bps: 2_000,
+            } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test fee exemptions - a full exemption waives the fee, a partial one discounts it
+    fn split_coins_fee_exemptions() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::AddFeeExemption {
+            addr: String::from("partner"),
+            discount_bps: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        let msg = ExecuteMsg::AddFeeExemption {
+            addr: String::from("friend"),
+            discount_bps: Some(5_000),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+
+        let split = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: None,
+        };
+        // fully exempt - 1000 split evenly, no fee
+        let info = mock_info("partner", &coins(1000, "usei"));
+        execute(deps.as_mut(), mock_env(), info, split.clone()).unwrap();
+        let fees = FEES
+            .may_load(&deps.storage, String::from("usei"))
+            .unwrap()
+            .unwrap_or_default();
+        assert_eq!(fees, Uint128::zero());
+        let alice = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, Uint128::new(500));
+
+        // half off - 10% of 1000 is 100, discounted to 50
+        let info = mock_info("friend", &coins(1000, "usei"));
+        execute(deps.as_mut(), mock_env(), info, split.clone()).unwrap();
+        let fees = FEES.load(&deps.storage, String::from("usei")).unwrap();
+        assert_eq!(fees, Uint128::new(50));
+
+        // the simulation applies the same discount
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetFee {
+                amount: Some(coin(1000, "usei")),
+                sender: Some(String::from("friend")),
+            },
+        )
+        .unwrap();
+        let value: FeeResponse = from_binary(&res).unwrap();
+        assert_eq!(value.simulated_fee, Some(coin(50, "usei")));
+
+        // once removed, the full fee applies again
+        let msg = ExecuteMsg::RemoveFeeExemption {
+            addr: String::from("partner"),
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        let info = mock_info("partner", &coins(1000, "usei"));
+        execute(deps.as_mut(), mock_env(), info, split).unwrap();
+        let fees = FEES.load(&deps.storage, String::from("usei")).unwrap();
+        assert_eq!(fees, Uint128::new(150));
+    }
+
+    #[test]
+    // Test only the owner manages fee exemptions, and discounts must be within (0, 10_000]
+    fn fee_exemptions_owner_only() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::AddFeeExemption {
+            addr: String::from("partner"),
+            discount_bps: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("partner", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        for discount_bps in [0, 10_001] {
+            let msg = ExecuteMsg::AddFeeExemption {
+                addr: String::from("partner"),
+                discount_bps: Some(discount_bps),
+            };
+            let res = execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg);
+            match res.unwrap_err() {
+                ContractError::InvalidParams {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+
+        let msg = ExecuteMsg::RemoveFeeExemption {
+            addr: String::from("partner"),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("partner", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test QueryMsg::ListFeeExemptions pages through exemptions in address order
+    fn list_fee_exemptions() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        for (addr, discount_bps) in [("carol", None), ("alice", Some(2_500)), ("bob", None)] {
+            let msg = ExecuteMsg::AddFeeExemption {
+                addr: String::from(addr),
+                discount_bps,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        }
+
+        let msg = QueryMsg::ListFeeExemptions {
+            start_after: None,
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: FeeExemptionsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value.exemptions,
+            vec![
+                FeeExemption {
+                    addr: Addr::unchecked("alice"),
+                    discount_bps: 2_500,
+                },
+                FeeExemption {
+                    addr: Addr::unchecked("bob"),
+                    discount_bps: 10_000,
+                },
+            ]
+        );
+
+        let msg = QueryMsg::ListFeeExemptions {
+            start_after: Some(String::from("bob")),
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: FeeExemptionsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value.exemptions,
+            vec![FeeExemption {
+                addr: Addr::unchecked("carol"),
+                discount_bps: 10_000,
+            }]
+        );
+    }
+
+    #[test]
+    // Test migrating 1.x state - the config is rewritten and usei balances move to the new wallets
+    fn migrate_from_v1() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "crates.io:sei-split-coins", "1.0.0").unwrap();
+        deps.storage.set(
+            b"config",
+            br#"{"owner":"eric","cw20_addr":"token","fee_percent":"25"}"#,
+        );
+        let legacy_wallets: cw_storage_plus::Map<Addr, Uint128> =
+            cw_storage_plus::Map::new("wallets");
+        legacy_wallets
+            .save(
+                &mut deps.storage,
+                Addr::unchecked("alice"),
+                &Uint128::new(100),
+            )
+            .unwrap();
+        legacy_wallets
+            .save(&mut deps.storage, Addr::unchecked("bob"), &Uint128::new(7))
+            .unwrap();
+        legacy_wallets
+            .save(
+                &mut deps.storage,
+                Addr::unchecked("carol"),
+                &Uint128::zero(),
+            )
+            .unwrap();
+        // 1.x credited fees to the wallet of cw20_addr
+        legacy_wallets
+            .save(
+                &mut deps.storage,
+                Addr::unchecked("token"),
+                &Uint128::new(12),
+            )
+            .unwrap();
+
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+        let config = CONFIG.load(&deps.storage).unwrap();
+        assert_eq!(config.owner, Some(Addr::unchecked("eric")));
+        assert_eq!(config.fee_collector, Addr::unchecked("eric"));
+        assert_eq!(
+            config.fee_schedule,
+            FeeSchedule::Percentage {
+                bps: 2_500,
+                min: None,
+                max: None,
+            }
+        );
+        assert_eq!(config.max_fee_bps, 2_500);
+
+        let res = query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::GetWallet {
+                addr: Addr::unchecked("alice"),
+            },
+        )
+        .unwrap();
+        let value: WalletResponse = from_binary(&res).unwrap();
+        assert_eq!(value.balances, coins(100, "usei"));
+        let bob = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("bob"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(bob, Uint128::new(7));
+        let fees = FEES.load(&deps.storage, String::from("usei")).unwrap();
+        assert_eq!(fees, Uint128::new(12));
+        for addr in ["carol", "token"] {
+            let wallet = WALLETS
+                .may_load(&deps.storage, (Addr::unchecked(addr), String::from("usei")))
+                .unwrap();
+            assert_eq!(wallet, None);
+        }
+
+        // the old entries are gone and the stored version is bumped
+        let legacy = legacy_wallets
+            .may_load(&deps.storage, Addr::unchecked("alice"))
+            .unwrap();
+        assert_eq!(legacy, None);
+        let version = cw2::get_contract_version(&deps.storage).unwrap();
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        // the running totals start from what the old wallets held
+        let totals = TOTALS.load(&deps.storage, String::from("usei")).unwrap();
+        assert_eq!(totals.owed, Uint128::new(119));
+        assert_eq!(totals.fees_accrued, Uint128::new(12));
+        assert_eq!(totals.withdrawn, Uint128::zero());
+
+        // migrating again at the same version is a no-op
+        migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+        assert_eq!(CONFIG.load(&deps.storage).unwrap(), config);
+    }
+
+    #[test]
+    // Test migrate refuses other contracts and downgrades
+    fn migrate_rejects_foreign_contract_and_downgrade() {
+        let mut deps = mock_dependencies();
+        cw2::set_contract_version(&mut deps.storage, "crates.io:cw20-base", "0.1.0").unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res.unwrap_err() {
+            ContractError::InvalidMigrationContract { contract } => {
+                assert_eq!(contract, "crates.io:cw20-base")
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        cw2::set_contract_version(&mut deps.storage, "crates.io:sei-split-coins", "99.0.0")
+            .unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res.unwrap_err() {
+            ContractError::MigrationDowngrade { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        cw2::set_contract_version(&mut deps.storage, "crates.io:sei-split-coins", "latest")
+            .unwrap();
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg {});
+        match res.unwrap_err() {
+            ContractError::InvalidVersion { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test QueryMsg::ListWallets pages through native and CW20 wallets, skipping empty ones
+    fn list_wallets() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+                &Uint128::new(10),
+            )
+            .unwrap();
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), String::from("uatom")),
+                &Uint128::new(5),
+            )
+            .unwrap();
+        // bob withdrew everything and should not be listed
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("bob"), String::from("usei")),
+                &Uint128::zero(),
+            )
+            .unwrap();
+        CW20_WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("carol"), Addr::unchecked("token")),
+                &Uint128::new(7),
+            )
+            .unwrap();
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("dave"), String::from("usei")),
+                &Uint128::new(1),
+            )
+            .unwrap();
+
+        let msg = QueryMsg::ListWallets {
+            start_after: None,
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: ListWalletsResponse = from_binary(&res).unwrap();
+        // addresses are length-prefixed in storage, so shorter ones come first
+        assert_eq!(
+            value.wallets,
+            vec![
+                WalletResponse {
+                    addr: Addr::unchecked("dave"),
+                    balances: coins(1, "usei"),
+                    cw20_balances: vec![],
+                },
+                WalletResponse {
+                    addr: Addr::unchecked("alice"),
+                    balances: vec![coin(5, "uatom"), coin(10, "usei")],
+                    cw20_balances: vec![],
+                },
+            ]
+        );
+        assert_eq!(value.next, Some(Addr::unchecked("alice")));
+
+        let msg = QueryMsg::ListWallets {
+            start_after: Some(String::from("alice")),
+            limit: Some(2),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: ListWalletsResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value.wallets,
+            vec![WalletResponse {
+                addr: Addr::unchecked("carol"),
+                balances: vec![],
+                cw20_balances: vec![Cw20CoinVerified {
+                    address: Addr::unchecked("token"),
+                    amount: Uint128::new(7),
+                }],
+            }]
+        );
+        assert_eq!(value.next, None);
+
+        // a zero limit still returns a page and a cursor to continue from
+        let msg = QueryMsg::ListWallets {
+            start_after: None,
+            limit: Some(0),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: ListWalletsResponse = from_binary(&res).unwrap();
+        assert_eq!(value.wallets.len(), 1);
+        assert_eq!(value.wallets[0].addr, Addr::unchecked("dave"));
+        assert_eq!(value.next, Some(Addr::unchecked("dave")));
+    }
+    #[test]
+    // Test push-mode splits send every share straight to its recipient
+    fn split_coins_push() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: Some(SplitOptions {
+                push: Some(true),
+                ..Default::default()
+            }),
+        };
+        let info = mock_info("sender", &coins(1000, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::reply_always(
+                    BankMsg::Send {
+                        to_address: String::from("alice"),
+                        amount: coins(450, "usei"),
+                    },
+                    1
+                ),
+                SubMsg::reply_always(
+                    BankMsg::Send {
+                        to_address: String::from("bob"),
+                        amount: coins(450, "usei"),
+                    },
+                    2
+                ),
+            ]
+        );
+        // nothing is credited, but the fee still is
+        let alice = WALLETS
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, None);
+        let fees = FEES.load(&deps.storage, String::from("usei")).unwrap();
+        assert_eq!(fees, Uint128::new(100));
+
+        // a delivered payout is simply cleared
+        let msg = Reply {
+            id: 1,
+            result: SubMsgResult::Ok(SubMsgResponse {
+                events: vec![],
+                data: None,
+            }),
+        };
+        reply(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(!PENDING_PAYOUTS.has(&deps.storage, 1));
+        let alice = WALLETS
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, None);
+
+        // a failed one falls back to the recipient's wallet
+        let msg = Reply {
+            id: 2,
+            result: SubMsgResult::Err(String::from("blocked address")),
+        };
+        reply(deps.as_mut(), mock_env(), msg).unwrap();
+        assert!(!PENDING_PAYOUTS.has(&deps.storage, 2));
+        let bob = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("bob"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(bob, Uint128::new(450));
+    }
+
+    #[test]
+    // Test the push_payouts config default, which a split's options override
+    fn split_coins_push_default() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: Some(true),
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let recipients = vec![
+            Recipient {
+                addr: String::from("alice"),
+                weight: 3,
+            },
+            Recipient {
+                addr: String::from("bob"),
+                weight: 1,
+            },
+        ];
+        let msg = ExecuteMsg::WeightedSplitCoins {
+            recipients: recipients.clone(),
+            options: None,
+        };
+        let info = mock_info("sender", &coins(100, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 2);
+
+        // opting out credits the wallets as usual
+        let msg = ExecuteMsg::WeightedSplitCoins {
+            recipients,
+            options: Some(SplitOptions {
+                push: Some(false),
+                ..Default::default()
+            }),
+        };
+        let info = mock_info("sender", &coins(100, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+        let alice = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, Uint128::new(75));
+
+        // and the owner can turn the default off again
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            push_payouts: Some(false),
+            ..Default::default()
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        assert!(!CONFIG.load(&deps.storage).unwrap().push_payouts);
+    }
+
+    #[test]
+    // Test withdrawing native coins and CW20 tokens to an address other than the caller's
+    fn withdraw_to_recipient() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+                &Uint128::new(100),
+            )
+            .unwrap();
+        CW20_WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), Addr::unchecked("token")),
+                &Uint128::new(50),
+            )
+            .unwrap();
+
+        // wallets written directly bypass the running totals
+        seed_totals(&mut deps.storage).unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+
+        let msg = ExecuteMsg::WithdrawCoins {
+            amount: Some(Uint128::new(40)),
+            denom: None,
+            recipient: Some(String::from("exchange")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("exchange"),
+                amount: coins(40, "usei"),
+            })
+        );
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "addr" && a.value == "alice"));
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "recipient" && a.value == "exchange"));
+        let alice = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, Uint128::new(60));
+
+        let msg = ExecuteMsg::WithdrawCw20 {
+            token: String::from("token"),
+            amount: None,
+            recipient: Some(String::from("multisig")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: String::from("token"),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: String::from("multisig"),
+                    amount: Uint128::new(50),
+                })
+                .unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // the recipient address must be valid
+        let msg = ExecuteMsg::WithdrawCoins {
+            amount: None,
+            denom: None,
+            recipient: Some(String::new()),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::Std(_) => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test a spender can withdraw from an owner's balance up to its allowance
+    fn withdraw_from_allowance() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+                &Uint128::new(100),
+            )
+            .unwrap();
+
+        // wallets written directly bypass the running totals
+        seed_totals(&mut deps.storage).unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+
+        // no allowance yet
+        let withdraw = |amount: u128| ExecuteMsg::WithdrawFrom {
+            owner: String::from("alice"),
+            amount: Some(Uint128::new(amount)),
+            denom: None,
+            recipient: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            withdraw(10),
+        );
+        match res.unwrap_err() {
+            ContractError::NoAllowance {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: String::from("bot"),
+            amount: Uint128::new(60),
+            denom: None,
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            withdraw(40),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("bot"),
+                amount: coins(40, "usei"),
+            })
+        );
+        let alice = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, Uint128::new(60));
+
+        let msg = QueryMsg::Allowance {
+            owner: String::from("alice"),
+            spender: String::from("bot"),
+            denom: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: AllowanceResponse = from_binary(&res).unwrap();
+        assert_eq!(value.allowance, Uint128::new(20));
+        assert_eq!(value.expires, Expiration::Never {});
+
+        // the balance would cover it, the allowance does not
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            withdraw(30),
+        );
+        match res.unwrap_err() {
+            ContractError::NoAllowance {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // spending the rest removes the allowance
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            withdraw(20),
+        )
+        .unwrap();
+        let msg = QueryMsg::AllAllowances {
+            owner: String::from("alice"),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: AllAllowancesResponse = from_binary(&res).unwrap();
+        assert_eq!(value.allowances, vec![]);
+    }
+
+    #[test]
+    // Test allowance expiry, decreases and listing, and that an account cannot approve itself
+    fn allowance_expiry_and_decrease() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+                &Uint128::new(100),
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: String::from("alice"),
+            amount: Uint128::new(10),
+            denom: None,
+            expires: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::CannotSetOwnAccount {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let env = mock_env();
+        let expires = Expiration::AtHeight(env.block.height + 10);
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: String::from("bot"),
+            amount: Uint128::new(50),
+            denom: None,
+            expires: Some(expires),
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: String::from("bot"),
+            amount: Uint128::new(5),
+            denom: Some(String::from("uatom")),
+            expires: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::DecreaseAllowance {
+            spender: String::from("bot"),
+            amount: Uint128::new(20),
+            denom: None,
+            expires: None,
+        };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+
+        let msg = QueryMsg::AllAllowances {
+            owner: String::from("alice"),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AllAllowancesResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value.allowances,
+            vec![
+                AllowanceInfo {
+                    spender: Addr::unchecked("bot"),
+                    denom: String::from("uatom"),
+                    allowance: Uint128::new(5),
+                    expires: Expiration::Never {},
+                },
+                AllowanceInfo {
+                    spender: Addr::unchecked("bot"),
+                    denom: String::from("usei"),
+                    allowance: Uint128::new(30),
+                    expires,
+                },
+            ]
+        );
+        let msg = QueryMsg::AllAllowances {
+            owner: String::from("alice"),
+            start_after: Some((String::from("bot"), String::from("uatom"))),
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: AllAllowancesResponse = from_binary(&res).unwrap();
+        assert_eq!(value.allowances.len(), 1);
+        assert_eq!(value.allowances[0].denom, "usei");
+
+        // once expired it can no longer be spent
+        let mut later = env;
+        later.block.height += 10;
+        let msg = ExecuteMsg::WithdrawFrom {
+            owner: String::from("alice"),
+            amount: Some(Uint128::new(10)),
+            denom: None,
+            recipient: Some(String::from("custody")),
+        };
+        let res = execute(deps.as_mut(), later, mock_info("bot", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::AllowanceExpired {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test Transfer moves a credited balance to another wallet without charging fees
+    fn transfer_between_wallets() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+                &Uint128::new(100),
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: String::from("bob"),
+            amount: Uint128::new(30),
+            denom: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(res.messages.len(), 0);
+        let alice = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, Uint128::new(70));
+        let bob = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("bob"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(bob, Uint128::new(30));
+        let fees = FEES.may_load(&deps.storage, String::from("usei")).unwrap();
+        assert_eq!(fees, None);
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: String::from("bob"),
+            amount: Uint128::new(71),
+            denom: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::InsufficientFunds {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: String::from("bob"),
+            amount: Uint128::zero(),
+            denom: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::Transfer {
+            recipient: String::from(MOCK_CONTRACT_ADDR),
+            amount: Uint128::new(10),
+            denom: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::ContractRecipient {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test TransferFrom moves an owner's balance against the spender's allowance
+    fn transfer_from_allowance() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), String::from("uatom")),
+                &Uint128::new(100),
+            )
+            .unwrap();
+
+        let transfer = |amount: u128| ExecuteMsg::TransferFrom {
+            owner: String::from("alice"),
+            recipient: String::from("bob"),
+            amount: Uint128::new(amount),
+            denom: Some(String::from("uatom")),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            transfer(10),
+        );
+        match res.unwrap_err() {
+            ContractError::NoAllowance {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: String::from("bot"),
+            amount: Uint128::new(25),
+            denom: Some(String::from("uatom")),
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            transfer(25),
+        )
+        .unwrap();
+        let bob = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("bob"), String::from("uatom")),
+            )
+            .unwrap();
+        assert_eq!(bob, Uint128::new(25));
+        let alice = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("uatom")),
+            )
+            .unwrap();
+        assert_eq!(alice, Uint128::new(75));
+
+        // the allowance is used up
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            transfer(1),
+        );
+        match res.unwrap_err() {
+            ContractError::NoAllowance {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::TransferFrom {
+            owner: String::from("alice"),
+            recipient: String::from(MOCK_CONTRACT_ADDR),
+            amount: Uint128::new(1),
+            denom: Some(String::from("uatom")),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bot", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::ContractRecipient {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test BatchPayout flushes the full balances of the listed accounts, one send per account
+    fn batch_payout_addrs() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        for (addr, denom, amount) in [
+            ("alice", "uatom", 5),
+            ("alice", "usei", 10),
+            ("bob", "usei", 20),
+            ("carol", "usei", 30),
+        ] {
+            WALLETS
+                .save(
+                    &mut deps.storage,
+                    (Addr::unchecked(addr), String::from(denom)),
+                    &Uint128::new(amount),
+                )
+                .unwrap();
+        }
+
+        // wallets written directly bypass the running totals
+        seed_totals(&mut deps.storage).unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(5, "uatom"), coin(60, "usei")]);
+
+        let msg = ExecuteMsg::BatchPayout {
+            addrs: Some(vec![
+                String::from("alice"),
+                String::from("bob"),
+                String::from("dave"),
+            ]),
+            start_after: None,
+            limit: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages
+                .iter()
+                .map(|m| m.msg.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("alice"),
+                    amount: vec![coin(5, "uatom"), coin(10, "usei")],
+                }),
+                CosmosMsg::Bank(BankMsg::Send {
+                    to_address: String::from("bob"),
+                    amount: coins(20, "usei"),
+                }),
+            ]
+        );
+        let alice = WALLETS
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, None);
+        let carol = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("carol"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(carol, Uint128::new(30));
+
+        // a cursor cannot be combined with an explicit list
+        let msg = ExecuteMsg::BatchPayout {
+            addrs: Some(vec![String::from("carol")]),
+            start_after: None,
+            limit: Some(1),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test BatchPayout pages through funded wallets and is limited to the owner and operators
+    fn batch_payout_cursor_and_operators() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        for (addr, amount) in [("anna", 1), ("bert", 0), ("cleo", 3), ("dina", 4)] {
+            WALLETS
+                .save(
+                    &mut deps.storage,
+                    (Addr::unchecked(addr), String::from("usei")),
+                    &Uint128::new(amount),
+                )
+                .unwrap();
+        }
+
+        // wallets written directly bypass the running totals
+        seed_totals(&mut deps.storage).unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(8, "usei"));
+
+        let batch = |start_after: Option<&str>| ExecuteMsg::BatchPayout {
+            addrs: None,
+            start_after: start_after.map(String::from),
+            limit: Some(2),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            batch(None),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            operators: Some(vec![String::from("bot")]),
+            ..Default::default()
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::BatchPayout {
+            addrs: None,
+            start_after: None,
+            limit: Some(0),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bot", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // bert has nothing to withdraw and is skipped
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            batch(None),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 2);
+        assert_eq!(
+            res.messages[1].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("cleo"),
+                amount: coins(3, "usei"),
+            })
+        );
+        let next = res.attributes.iter().find(|a| a.key == "next").unwrap();
+        assert_eq!(next.value, "cleo");
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("bot", &[]),
+            batch(Some("cleo")),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 1);
+        assert!(!res.attributes.iter().any(|a| a.key == "next"));
+
+        // everything has been paid out
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("eric", &[]),
+            batch(None),
+        )
+        .unwrap();
+        assert_eq!(res.messages.len(), 0);
+    }
+
+    #[test]
+    // Test splitting to a stored group uses its recipients and weights, for coins and CW20 tokens
+    fn split_to_group() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::CreateSplitGroup {
+            name: String::from("royalties"),
+            recipients: vec![
+                Recipient {
+                    addr: String::from("artist"),
+                    weight: 9,
+                },
+                Recipient {
+                    addr: String::from("label"),
+                    weight: 1,
+                },
+            ],
+            mutable: false,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("label", &[]), msg).unwrap();
+
+        let msg = ExecuteMsg::SplitToGroup {
+            group_id: 1,
+            options: None,
+        };
+        let info = mock_info("buyer", &coins(100, "usei"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let artist = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("artist"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(artist, Uint128::new(90));
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("buyer"),
+            amount: Uint128::new(50),
+            msg: to_binary(&ReceiveMsg::SplitToGroup { group_id: 1 }).unwrap(),
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("token", &[]), msg).unwrap();
+        let label = CW20_WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("label"), Addr::unchecked("token")),
+            )
+            .unwrap();
+        assert_eq!(label, Uint128::new(5));
+
+        // unknown groups cannot be split to
+        let msg = ExecuteMsg::SplitToGroup {
+            group_id: 2,
+            options: None,
+        };
+        let info = mock_info("buyer", &coins(100, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg);
+        match res.unwrap_err() {
+            ContractError::Std(_) => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test only the creator can update or delete a group, and immutable groups cannot change
+    fn split_group_management() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let recipients = vec![Recipient {
+            addr: String::from("alice"),
+            weight: 1,
+        }];
+        for mutable in [true, false] {
+            let msg = ExecuteMsg::CreateSplitGroup {
+                name: String::from("team"),
+                recipients: recipients.clone(),
+                mutable,
+            };
+            execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        }
+        // a zero weight is rejected up front
+        let msg = ExecuteMsg::CreateSplitGroup {
+            name: String::from("broken"),
+            recipients: vec![Recipient {
+                addr: String::from("bob"),
+                weight: 0,
+            }],
+            mutable: true,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let update = |group_id: u64| ExecuteMsg::UpdateSplitGroup {
+            group_id,
+            name: None,
+            recipients: Some(vec![Recipient {
+                addr: String::from("bob"),
+                weight: 2,
+            }]),
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), update(1));
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            update(2),
+        );
+        match res.unwrap_err() {
+            ContractError::ImmutableGroup { group_id: 2 } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            update(1),
+        )
+        .unwrap();
+
+        let msg = QueryMsg::GetSplitGroup { group_id: 1 };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let group: SplitGroup = from_binary(&res).unwrap();
+        assert_eq!(group.creator, Addr::unchecked("alice"));
+        assert_eq!(group.recipients[0].addr, "bob");
+
+        let msg = ExecuteMsg::DeleteSplitGroup { group_id: 1 };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let msg = QueryMsg::ListSplitGroups {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: SplitGroupsResponse = from_binary(&res).unwrap();
+        assert_eq!(value.groups.len(), 1);
+        assert_eq!(value.groups[0].id, 2);
+        assert!(!value.groups[0].mutable);
+    }
+
+    #[test]
+    // Test vesting splits release shares linearly after the cliff, and only vested funds withdraw
+    fn split_coins_vesting() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let env = mock_env();
+        let start = env.block.time;
+        let vesting = Vesting {
+            start,
+            end: start.plus_seconds(1_000),
+            cliff: Some(start.plus_seconds(250)),
+        };
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: Some(SplitOptions {
+                vesting: Some(vesting.clone()),
+                ..Default::default()
+            }),
+        };
+        let info = mock_info("grantor", &coins(2000, "usei"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+        // nothing is withdrawable before the cliff
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(2000, "usei"));
+        let withdraw = ExecuteMsg::WithdrawCoins {
+            amount: None,
+            denom: None,
+            recipient: None,
+        };
+        let mut now = env.clone();
+        now.block.time = start.plus_seconds(100);
+        let res = execute(
+            deps.as_mut(),
+            now.clone(),
+            mock_info("alice", &[]),
+            withdraw.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::InsufficientFunds {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // at the cliff everything vested so far is released
+        now.block.time = start.plus_seconds(250);
+        let msg = QueryMsg::GetVesting {
+            addr: String::from("alice"),
+        };
+        let res = query(deps.as_ref(), now.clone(), msg).unwrap();
+        let value: VestingResponse = from_binary(&res).unwrap();
+        assert_eq!(value.grants.len(), 1);
+        assert_eq!(value.grants[0].total, Uint128::new(1000));
+        assert_eq!(value.grants[0].vested, Uint128::new(250));
+        assert_eq!(value.grants[0].vesting, vesting);
+
+        let res = execute(
+            deps.as_mut(),
+            now.clone(),
+            mock_info("alice", &[]),
+            withdraw.clone(),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("alice"),
+                amount: coins(250, "usei"),
+            })
+        );
+
+        // once fully vested the grant is gone
+        now.block.time = start.plus_seconds(5_000);
+        let res = execute(
+            deps.as_mut(),
+            now.clone(),
+            mock_info("alice", &[]),
+            withdraw,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("alice"),
+                amount: coins(750, "usei"),
+            })
+        );
+        let msg = QueryMsg::GetVesting {
+            addr: String::from("alice"),
+        };
+        let res = query(deps.as_ref(), now, msg).unwrap();
+        let value: VestingResponse = from_binary(&res).unwrap();
+        assert_eq!(value.grants, vec![]);
+    }
+
+    #[test]
+    // Test a vesting schedule must end after it starts, with any cliff in between
+    fn split_coins_vesting_invalid() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let start = mock_env().block.time;
+        let invalid = vec![
+            Vesting {
+                start: start.plus_seconds(10),
+                end: start,
+                cliff: None,
+            },
+            Vesting {
+                start,
+                end: start.plus_seconds(10),
+                cliff: Some(start.plus_seconds(20)),
+            },
+        ];
+        for vesting in invalid {
+            let msg = ExecuteMsg::SplitCoins {
+                target_addr1: String::from("alice"),
+                target_addr2: String::from("bob"),
+                options: Some(SplitOptions {
+                    vesting: Some(vesting),
+                    ..Default::default()
+                }),
+            };
+            let info = mock_info("grantor", &coins(100, "usei"));
+            let res = execute(deps.as_mut(), mock_env(), info, msg);
+            match res.unwrap_err() {
+                ContractError::InvalidParams {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    // Test expiring splits - recipients claim before the deadline, the sender reclaims the rest
+    fn split_coins_expiring_claim_and_reclaim() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let env = mock_env();
+        let expires = Expiration::AtHeight(env.block.height + 100);
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: Some(SplitOptions {
+                expires: Some(expires),
+                ..Default::default()
+            }),
+        };
+        let info = mock_info("sender", &coins(100, "usei"));
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "split_id" && a.value == "1"));
+        // shares are held until claimed
+        let alice = WALLETS
+            .may_load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, None);
+
+        let msg = ExecuteMsg::Claim { split_id: 1 };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        let alice = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, Uint128::new(50));
+        let msg = ExecuteMsg::Claim { split_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::NothingToClaim {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the sender has to wait for the deadline
+        let msg = ExecuteMsg::Reclaim { split_id: 1 };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("sender", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::ClaimNotExpired {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let mut later = env.clone();
+        later.block.height += 100;
+        let msg = ExecuteMsg::Claim { split_id: 1 };
+        let res = execute(deps.as_mut(), later.clone(), mock_info("bob", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::ClaimExpired {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+        let msg = ExecuteMsg::Reclaim { split_id: 1 };
+        let res = execute(deps.as_mut(), later.clone(), mock_info("bob", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        let msg = ExecuteMsg::Reclaim { split_id: 1 };
+        let res = execute(deps.as_mut(), later.clone(), mock_info("sender", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("sender"),
+                amount: coins(50, "usei"),
+            })
+        );
+        let msg = ExecuteMsg::Reclaim { split_id: 1 };
+        let res = execute(deps.as_mut(), later.clone(), mock_info("sender", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::NothingToClaim {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // the escrow is gone once reclaimed, but the split record stays
+        let res = query(
+            deps.as_ref(),
+            later.clone(),
+            QueryMsg::GetEscrow { split_id: 1 },
+        );
+        assert!(res.is_err());
+        let res = query(deps.as_ref(), later, QueryMsg::GetSplit { split_id: 1 }).unwrap();
+        let record: SplitRecord = from_binary(&res).unwrap();
+        assert_eq!(record.sender, Addr::unchecked("sender"));
+        assert_eq!(record.shares.len(), 2);
+
+        // an escrow is also removed once every share is claimed
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: Some(SplitOptions {
+                expires: Some(expires),
+                ..Default::default()
+            }),
+        };
+        let info = mock_info("sender", &coins(100, "usei"));
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        let msg = ExecuteMsg::Claim { split_id: 2 };
+        execute(deps.as_mut(), env.clone(), mock_info("alice", &[]), msg).unwrap();
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetEscrow { split_id: 2 },
+        )
+        .unwrap();
+        let escrow: Escrow = from_binary(&res).unwrap();
+        assert!(escrow.shares[0].claimed);
+        assert!(!escrow.shares[1].claimed);
+        let msg = ExecuteMsg::Claim { split_id: 2 };
+        execute(deps.as_mut(), env.clone(), mock_info("bob", &[]), msg).unwrap();
+        let msg = QueryMsg::ListEscrows {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: EscrowsResponse = from_binary(&res).unwrap();
+        assert_eq!(value.escrows, vec![]);
+    }
+
+    #[test]
+    // Test an expiring split needs a future deadline and cannot also vest
+    fn split_coins_expiring_invalid() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let env = mock_env();
+        let invalid = vec![
+            SplitOptions {
+                expires: Some(Expiration::AtHeight(env.block.height)),
+                ..Default::default()
+            },
+            SplitOptions {
+                expires: Some(Expiration::Never {}),
+                ..Default::default()
+            },
+            SplitOptions {
+                expires: Some(Expiration::AtHeight(env.block.height + 10)),
+                vesting: Some(Vesting {
+                    start: env.block.time,
+                    end: env.block.time.plus_seconds(10),
+                    cliff: None,
+                }),
+                ..Default::default()
+            },
+        ];
+        for options in invalid {
+            let msg = ExecuteMsg::SplitCoins {
+                target_addr1: String::from("alice"),
+                target_addr2: String::from("bob"),
+                options: Some(options),
+            };
+            let info = mock_info("sender", &coins(100, "usei"));
+            let res = execute(deps.as_mut(), env.clone(), info, msg);
+            match res.unwrap_err() {
+                ContractError::InvalidParams {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+
+        let msg = QueryMsg::ListEscrows {
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: EscrowsResponse = from_binary(&res).unwrap();
+        assert_eq!(value.escrows, vec![]);
+    }
+
+    #[test]
+    // Test every split is recorded and can be listed by sender and by recipient
+    fn split_history() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(100),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let splits = vec![
+            ("sender", "alice", "bob"),
+            ("sender", "alice", "carol"),
+            ("other", "bob", "carol"),
+        ];
+        for (sender, addr1, addr2) in splits {
+            let msg = ExecuteMsg::SplitCoins {
+                target_addr1: String::from(addr1),
+                target_addr2: String::from(addr2),
+                options: None,
+            };
+            let info = mock_info(sender, &coins(1000, "usei"));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let env = mock_env();
+        let res = query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::GetSplit { split_id: 1 },
+        )
+        .unwrap();
+        let record: SplitRecord = from_binary(&res).unwrap();
+        assert_eq!(record.sender, Addr::unchecked("sender"));
+        assert_eq!(record.token, None);
+        assert_eq!(record.amount, coins(990, "usei"));
+        assert_eq!(record.fees, coins(10, "usei"));
+        assert_eq!(record.shares[0].recipient, Addr::unchecked("alice"));
+        assert_eq!(record.shares[0].amount, coins(495, "usei"));
+        assert_eq!(record.height, env.block.height);
+        assert_eq!(record.time, env.block.time);
+
+        let msg = QueryMsg::ListSplitsBySender {
+            sender: String::from("sender"),
+            start_after: None,
+            limit: Some(1),
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: SplitsResponse = from_binary(&res).unwrap();
+        let ids: Vec<u64> = value.splits.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![1]);
+        let msg = QueryMsg::ListSplitsBySender {
+            sender: String::from("sender"),
+            start_after: Some(1),
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: SplitsResponse = from_binary(&res).unwrap();
+        let ids: Vec<u64> = value.splits.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![2]);
+
+        let msg = QueryMsg::ListSplitsByRecipient {
+            recipient: String::from("carol"),
+            start_after: None,
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let value: SplitsResponse = from_binary(&res).unwrap();
+        let ids: Vec<u64> = value.splits.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![2, 3]);
+        let msg = QueryMsg::ListSplitsByRecipient {
+            recipient: String::from("bob"),
+            start_after: Some(1),
+            limit: None,
+        };
+        let res = query(deps.as_ref(), env, msg).unwrap();
+        let value: SplitsResponse = from_binary(&res).unwrap();
+        let ids: Vec<u64> = value.splits.iter().map(|s| s.id).collect();
+        assert_eq!(ids, vec![3]);
+    }
+
+    #[test]
+    // Test the guardian can pause splits and withdrawals independently, and only the owner unpauses
+    fn pause_splits_and_withdrawals() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let split = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: None,
+        };
+        let info = mock_info("sender", &coins(100, "usei"));
+        execute(deps.as_mut(), mock_env(), info, split.clone()).unwrap();
+
+        // only the owner picks the guardian, and nobody else may pause
+        let msg = ExecuteMsg::Pause {
+            splits: true,
+            withdrawals: false,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guard", &[]),
+            msg.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let set_guardian = ExecuteMsg::SetGuardian {
+            guardian: Some(String::from("guard")),
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guard", &[]),
+            set_guardian.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("eric", &[]),
+            set_guardian,
+        )
+        .unwrap();
+        execute(deps.as_mut(), mock_env(), mock_info("guard", &[]), msg).unwrap();
+
+        let res = query(deps.as_ref(), mock_env(), QueryMsg::PauseStatus {}).unwrap();
+        let value: PauseStatusResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value,
+            PauseStatusResponse {
+                splits_paused: true,
+                withdrawals_paused: false,
+                guardian: Some(Addr::unchecked("guard")),
+            }
+        );
+
+        // deposits are blocked but withdrawals still go through
+        let info = mock_info("sender", &coins(100, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, split.clone());
+        match res.unwrap_err() {
+            ContractError::Paused {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+        let withdraw = ExecuteMsg::WithdrawCoins {
+            amount: Some(Uint128::new(10)),
+            denom: None,
+            recipient: None,
+        };
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            withdraw.clone(),
+        )
+        .unwrap();
+
+        let msg = ExecuteMsg::Pause {
+            splits: false,
+            withdrawals: true,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            withdraw.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Paused {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        // moving funds inside the ledger is still allowed
+        let msg = ExecuteMsg::Transfer {
+            recipient: String::from("bob"),
+            amount: Uint128::new(10),
+            denom: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        // the guardian cannot lift the pause
+        let msg = ExecuteMsg::Unpause {
+            splits: true,
+            withdrawals: false,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("guard", &[]),
+            msg.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+
+        let info = mock_info("sender", &coins(100, "usei"));
+        execute(deps.as_mut(), mock_env(), info, split).unwrap();
+        let res = execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), withdraw);
+        match res.unwrap_err() {
+            ContractError::Paused {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+    }
+
+    #[test]
+    // Test the running totals follow splits and withdrawals, and payouts stop once the balance drifts
+    fn solvency_totals() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: None,
+        };
+        let info = mock_info("sender", &coins(100, "usei"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+
+        let msg = ExecuteMsg::WithdrawCoins {
+            amount: None,
+            denom: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(55, "usei"));
+
+        let msg = QueryMsg::Solvency { denom: None };
+        let res = query(deps.as_ref(), mock_env(), msg.clone()).unwrap();
+        let value: SolvencyResponse = from_binary(&res).unwrap();
+        assert_eq!(
+            value,
+            SolvencyResponse {
+                denom: String::from("usei"),
+                balance: Uint128::new(55),
+                totals: Totals {
+                    deposited: Uint128::new(100),
+                    owed: Uint128::new(55),
+                    fees_accrued: Uint128::new(10),
+                    withdrawn: Uint128::new(45),
+                },
+                solvent: true,
+            }
+        );
+
+        // the balance no longer covers the ledger - nothing more may leave
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(54, "usei"));
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let value: SolvencyResponse = from_binary(&res).unwrap();
+        assert!(!value.solvent);
+        let msg = ExecuteMsg::WithdrawCoins {
+            amount: Some(Uint128::new(1)),
+            denom: None,
+            recipient: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("bob", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::Insolvent {
+                denom,
+                owed,
+                balance,
+            } => {
+                assert_eq!(denom, "usei");
+                assert_eq!(owed, Uint128::new(55));
+                assert_eq!(balance, Uint128::new(54));
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let msg = ExecuteMsg::WithdrawFees {
+            recipient: None,
+            token: None,
+        };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg);
+        match res.unwrap_err() {
+            ContractError::Insolvent { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let bob = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("bob"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(bob, Uint128::new(45));
+    }
+
+    #[test]
+    // Test SweepSurplus only sends what the contract holds beyond its liabilities
+    fn sweep_surplus() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: Some(1_000),
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: None,
+        };
+        let info = mock_info("sender", &coins(100, "usei"));
+        execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        // nothing beyond the split and its fee is held yet
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(100, "usei"));
+        let msg = ExecuteMsg::SweepSurplus {
+            recipient: Some(String::from("partner")),
+            denom: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("eric", &[]),
+            msg.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::InsufficientFunds {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // 25usei bank-sent straight to the contract
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, coins(125, "usei"));
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("alice", &[]),
+            msg.clone(),
+        );
+        match res.unwrap_err() {
+            ContractError::Unauthorized {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let res = execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("partner"),
+                amount: coins(25, "usei"),
+            })
+        );
+        let totals = TOTALS.load(&deps.storage, String::from("usei")).unwrap();
+        assert_eq!(totals.owed, Uint128::new(100));
+        assert_eq!(totals.withdrawn, Uint128::zero());
+    }
+
+    #[test]
+    // Test each remainder policy, set per split or in the config
+    fn split_remainder_policies() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let even = |remainder: Option<RemainderPolicy>| ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: Some(SplitOptions {
+                remainder,
+                ..Default::default()
+            }),
+        };
+        let weighted = ExecuteMsg::WeightedSplitCoins {
+            recipients: vec![
+                Recipient {
+                    addr: String::from("alice"),
+                    weight: 1,
+                },
+                Recipient {
+                    addr: String::from("bob"),
+                    weight: 2,
+                },
+                Recipient {
+                    addr: String::from("carol"),
+                    weight: 2,
+                },
+            ],
+            options: Some(SplitOptions {
+                remainder: Some(RemainderPolicy::LargestWeight),
+                ..Default::default()
+            }),
+        };
+        let msgs = vec![
+            even(None),
+            weighted,
+            even(Some(RemainderPolicy::RoundRobin)),
+            even(Some(RemainderPolicy::RoundRobin)),
+            even(Some(RemainderPolicy::FeeCollector)),
+            even(Some(RemainderPolicy::Bucket)),
+            even(Some(RemainderPolicy::Bucket)),
+        ];
+        for msg in msgs {
+            let info = mock_info("sender", &coins(101, "usei"));
+            execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        }
+
+        let shares = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, split_id: u64| -> Vec<u128> {
+            splits()
+                .load(&deps.storage, split_id)
+                .unwrap()
+                .shares
+                .iter()
+                .map(|s| s.amount[0].amount.u128())
+                .collect()
+        };
+        // first recipient by default, then the first of the heaviest
+        assert_eq!(shares(&deps, 1), vec![51, 50]);
+        assert_eq!(shares(&deps, 2), vec![20, 41, 40]);
+        // round robin carries on from the last split
+        assert_eq!(shares(&deps, 3), vec![51, 50]);
+        assert_eq!(shares(&deps, 4), vec![50, 51]);
+        // the fee collector keeps the odd unit
+        assert_eq!(shares(&deps, 5), vec![50, 50]);
+        let record = splits().load(&deps.storage, 5).unwrap();
+        assert_eq!(record.fees, coins(1, "usei"));
+        assert_eq!(record.amount, coins(100, "usei"));
+        // the bucket holds it until the next split divides evenly
+        assert_eq!(shares(&deps, 6), vec![50, 50]);
+        assert_eq!(shares(&deps, 7), vec![51, 51]);
+        let bucket = REMAINDERS
+            .load(&deps.storage, String::from("usei"))
+            .unwrap();
+        assert_eq!(bucket, Uint128::zero());
+
+        // the configured policy applies when a split doesn't choose
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            remainder_policy: Some(RemainderPolicy::FeeCollector),
+            ..Default::default()
+        });
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        let info = mock_info("sender", &coins(101, "usei"));
+        execute(deps.as_mut(), mock_env(), info, even(None)).unwrap();
+        assert_eq!(shares(&deps, 8), vec![50, 50]);
+        let fees = FEES.load(&deps.storage, String::from("usei")).unwrap();
+        assert_eq!(fees, Uint128::new(2));
+    }
+
+    #[test]
+    // Test splits below the minimum deposit or leaving a recipient with nothing are rejected
+    fn split_minimum_and_dust() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: Some(coins(10, "uatom")),
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        let mut invalid = msg.clone();
+        invalid.min_deposits = Some(coins(0, "uatom"));
+        let res = instantiate(deps.as_mut(), mock_env(), info.clone(), invalid);
+        match res.unwrap_err() {
+            ContractError::InvalidParams {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let split = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("bob"),
+            options: None,
+        };
+        let info = mock_info("sender", &[coin(9, "uatom"), coin(100, "usei")]);
+        let res = execute(deps.as_mut(), mock_env(), info, split.clone());
+        match res.unwrap_err() {
+            ContractError::DepositTooSmall { denom, min } => {
+                assert_eq!(denom, "uatom");
+                assert_eq!(min, Uint128::new(10));
+            }
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // usei has no minimum, but 1usei cannot reach both recipients
+        let info = mock_info("sender", &coins(1, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, split.clone());
+        match res.unwrap_err() {
+            ContractError::ZeroShare { recipient } => assert_eq!(recipient, "bob"),
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // zero amounts, blank denoms and repeated denoms are rejected
+        for min_deposits in [
+            vec![coin(0, "usei")],
+            vec![coin(10, " ")],
+            vec![coin(10, "uatom"), coin(20, "uatom")],
+        ] {
+            let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+                min_deposits: Some(min_deposits),
+                ..Default::default()
+            });
+            let res = execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg);
+            match res.unwrap_err() {
+                ContractError::InvalidParams {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+
+        // the minimum can be raised for usei too
+        let msg = ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+            min_deposits: Some(vec![coin(10, "uatom"), coin(1_000, "usei")]),
+            ..Default::default()
+        });
+        let res = execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        let attr = |key: &str| {
+            res.attributes
+                .iter()
+                .find(|a| a.key == key)
+                .unwrap()
+                .value
+                .clone()
+        };
+        assert_eq!(attr("min_deposits"), "10uatom,1000usei");
+        assert_eq!(attr("remainder_policy"), "first");
+        assert_eq!(
+            attr("recipient_policy"),
+            "duplicates=allow,forbid_self=false"
+        );
+        let info = mock_info("sender", &coins(999, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, split.clone());
+        match res.unwrap_err() {
+            ContractError::DepositTooSmall { .. } => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let info = mock_info("sender", &coins(1_000, "usei"));
+        execute(deps.as_mut(), mock_env(), info, split).unwrap();
+
+        // no fee was charged, so no fee entry is written
+        assert_eq!(
+            FEES.may_load(&deps.storage, String::from("usei")).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    // Test withdrawing a whole CW20 balance removes the wallet entry
+    fn withdraw_cw20_removes_empty_wallet() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from(MOCK_CONTRACT_ADDR),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        CW20_WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), Addr::unchecked("token")),
+                &Uint128::new(50),
+            )
+            .unwrap();
+
+        let msg = ExecuteMsg::WithdrawCw20 {
+            token: String::from("token"),
+            amount: Some(Uint128::new(20)),
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        let key = (Addr::unchecked("alice"), Addr::unchecked("token"));
+        let balance = CW20_WALLETS.load(&deps.storage, key.clone()).unwrap();
+        assert_eq!(balance, Uint128::new(30));
+
+        let msg = ExecuteMsg::WithdrawCw20 {
+            token: String::from("token"),
+            amount: None,
+            recipient: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(CW20_WALLETS.may_load(&deps.storage, key).unwrap(), None);
+    }
+
+    #[test]
+    // Test recipient rules - the contract is never a recipient, duplicates and the sender per policy
+    fn split_recipient_policy() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info("eric", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let split = |addr1: &str, addr2: &str| ExecuteMsg::SplitCoins {
+            target_addr1: String::from(addr1),
+            target_addr2: String::from(addr2),
+            options: None,
+        };
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(100, "usei")),
+            split("alice", MOCK_CONTRACT_ADDR),
+        );
+        match res.unwrap_err() {
+            ContractError::ContractRecipient {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // by default duplicates and the sender are paid like anyone else
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(100, "usei")),
+            split("alice", "alice"),
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(100, "usei")),
+            split("sender", "bob"),
+        )
+        .unwrap();
+
+        let set_policy = |duplicates: DuplicatePolicy, forbid_self: bool| {
+            ExecuteMsg::UpdateConfig(UpdateConfigMsg {
+                recipient_policy: Some(RecipientPolicy {
+                    duplicates,
+                    forbid_self,
+                }),
+                ..Default::default()
+            })
+        };
+        let msg = set_policy(DuplicatePolicy::Reject, true);
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(100, "usei")),
+            split("alice", "alice"),
+        );
+        match res.unwrap_err() {
+            ContractError::DuplicateRecipient { recipient } => assert_eq!(recipient, "alice"),
+            e => panic!("unexpected error: {:?}", e),
+        }
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("sender", &coins(100, "usei")),
+            split("sender", "bob"),
+        );
+        match res.unwrap_err() {
+            ContractError::SelfRecipient {} => {}
+            e => panic!("unexpected error: {:?}", e),
+        }
+
+        // merged duplicates become one recipient with the combined weight
+        let msg = set_policy(DuplicatePolicy::Merge, false);
+        execute(deps.as_mut(), mock_env(), mock_info("eric", &[]), msg).unwrap();
+        let msg = ExecuteMsg::WeightedSplitCoins {
+            recipients: vec![
+                Recipient {
+                    addr: String::from("carol"),
+                    weight: 1,
+                },
+                Recipient {
+                    addr: String::from("dave"),
+                    weight: 2,
+                },
+                Recipient {
+                    addr: String::from("carol"),
+                    weight: 1,
+                },
+            ],
+            options: None,
+        };
+        let info = mock_info("sender", &coins(100, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        let split_id = res
+            .attributes
+            .iter()
+            .find(|a| a.key == "split_id")
+            .unwrap()
+            .value
+            .parse::<u64>()
+            .unwrap();
+        let record = splits().load(&deps.storage, split_id).unwrap();
+        assert_eq!(record.shares.len(), 2);
+        assert_eq!(record.shares[0].recipient, Addr::unchecked("carol"));
+        assert_eq!(record.shares[0].amount, coins(50, "usei"));
+        assert_eq!(record.shares[1].amount, coins(50, "usei"));
+    }
+
+    #[test]
+    // Test SplitCoins to the same target twice under DuplicatePolicy::Merge, for coins and CW20 tokens
+    fn split_coins_merged_duplicates() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: Some(RecipientPolicy {
+                duplicates: DuplicatePolicy::Merge,
+                forbid_self: false,
+            }),
+        };
+        let info = mock_info("eric", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let msg = ExecuteMsg::SplitCoins {
+            target_addr1: String::from("alice"),
+            target_addr2: String::from("alice"),
+            options: None,
+        };
+        let info = mock_info("sender", &coins(101, "usei"));
+        let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+        // the merged target is reported twice, with nothing left for the second listing
+        let attr = |res: &cosmwasm_std::Response, key: &str| {
+            res.attributes
+                .iter()
+                .find(|a| a.key == key)
+                .unwrap()
+                .value
+                .clone()
+        };
+        assert_eq!(attr(&res, "target_addr1"), "alice");
+        assert_eq!(attr(&res, "target_addr1_amount"), "101usei");
+        assert_eq!(attr(&res, "target_addr2"), "alice");
+        assert_eq!(attr(&res, "target_addr2_amount"), "0usei");
+        let alice = WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+            )
+            .unwrap();
+        assert_eq!(alice, Uint128::new(101));
+
+        let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: String::from("sender"),
+            amount: Uint128::new(100),
+            msg: to_binary(&ReceiveMsg::SplitCoins {
+                target_addr1: String::from("bob"),
+                target_addr2: String::from("bob"),
+            })
+            .unwrap(),
+        });
+        let res = execute(deps.as_mut(), mock_env(), mock_info("token", &[]), msg).unwrap();
+        assert_eq!(attr(&res, "target_addr1"), "bob");
+        assert_eq!(attr(&res, "target_addr1_amount"), "100");
+        assert_eq!(attr(&res, "target_addr2"), "bob");
+        assert_eq!(attr(&res, "target_addr2_amount"), "0");
+        // CW20 splits are recorded like native ones
+        assert!(res
+            .attributes
+            .iter()
+            .any(|a| a.key == "split_id" && a.value == "2"));
+        let bob = CW20_WALLETS
+            .load(
+                &deps.storage,
+                (Addr::unchecked("bob"), Addr::unchecked("token")),
+            )
+            .unwrap();
+        assert_eq!(bob, Uint128::new(100));
+    }
+
+    #[test]
+    // Test withdrawals to the contract's own address are rejected
+    fn withdraw_to_contract() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info("eric", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+        WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), String::from("usei")),
+                &Uint128::new(100),
+            )
+            .unwrap();
+        CW20_WALLETS
+            .save(
+                &mut deps.storage,
+                (Addr::unchecked("alice"), Addr::unchecked("token")),
+                &Uint128::new(100),
+            )
+            .unwrap();
+        let msg = ExecuteMsg::IncreaseAllowance {
+            spender: String::from("bot"),
+            amount: Uint128::new(100),
+            denom: None,
+            expires: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("alice", &[]), msg).unwrap();
+
+        let contract = || Some(String::from(MOCK_CONTRACT_ADDR));
+        let cases = vec![
+            (
+                "alice",
+                ExecuteMsg::WithdrawCoins {
+                    amount: None,
+                    denom: None,
+                    recipient: contract(),
+                },
+            ),
+            (
+                "bot",
+                ExecuteMsg::WithdrawFrom {
+                    owner: String::from("alice"),
+                    amount: None,
+                    denom: None,
+                    recipient: contract(),
+                },
+            ),
+            (
+                "alice",
+                ExecuteMsg::WithdrawCw20 {
+                    token: String::from("token"),
+                    amount: None,
+                    recipient: contract(),
+                },
+            ),
+            (
+                "eric",
+                ExecuteMsg::WithdrawFees {
+                    recipient: contract(),
+                    token: None,
+                },
+            ),
+        ];
+        for (sender, msg) in cases {
+            let res = execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg);
+            match res.unwrap_err() {
+                ContractError::ContractRecipient {} => {}
+                e => panic!("unexpected error: {:?}", e),
+            }
+        }
+    }
+
+    #[test]
+    // Test withdrawals only release grants of their denom, and recipients hold a bounded number of grants
+    fn vesting_grant_limits() {
+        let mut deps = mock_dependencies();
+        let msg = InstantiateMsg {
+            owner: Some(String::from("eric")),
+            cw20_addr: String::from("token"),
+            allowed_cw20s: None,
+            fee_collector: None,
+            fee_bps: None,
+            fee_schedule: None,
+            max_fee_bps: None,
+            allowed_denoms: None,
+            push_payouts: None,
+            remainder_policy: None,
+            min_deposits: None,
+            recipient_policy: None,
+        };
+        let info = mock_info("eric", &[]);
+        instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+        let env = mock_env();
+        let options = Some(SplitOptions {
+            vesting: Some(Vesting {
+                start: env.block.time,
+                end: env.block.time.plus_seconds(1_000),
+                cliff: None,
+            }),
+            ..Default::default()
+        });
+        let split = |count: usize| ExecuteMsg::WeightedSplitCoins {
+            recipients: vec![
+                Recipient {
+                    addr: String::from("alice"),
+                    weight: 1,
+                };
+                count
+            ],
+            options: options.clone(),
+        };
+        let info = mock_info("grantor", &[coin(10, "uatom"), coin(10, "usei")]);
+        execute(deps.as_mut(), env.clone(), info, split(1)).unwrap();
+        let info = mock_info("grantor", &coins(18, "usei"));
+        execute(deps.as_mut(), env.clone(), info, split(18)).unwrap();
+
+        // withdrawing usei releases the usei grants and leaves the uatom one vesting
+        let mut later = env.clone();
+        later.block.time = env.block.time.plus_seconds(1_000);
+        deps.querier.update_balance(
+            MOCK_CONTRACT_ADDR,
+            vec![coin(10, "uatom"), coin(28, "usei")],
+        );
+        let msg = ExecuteMsg::WithdrawCoins {
+            amount: None,
+            denom: None,
+            recipient: None,
+        };
+        let res = execute(deps.as_mut(), later.clone(), mock_info("alice", &[]), msg).unwrap();
+        assert_eq!(
+            res.messages[0].msg,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: String::from("alice"),
+                amount: coins(28, "usei"),
+            })
+        );
+        let msg = QueryMsg::GetVesting {
+            addr: String::from("alice"),
+        };
+        let res = query(deps.as_ref(), later.clone(), msg).unwrap();
+        let value: VestingResponse = from_binary(&res).unwrap();
+        assert_eq!(value.grants.len(), 1);
+        assert_eq!(value.grants[0].denom, "uatom");
+
+        // released grants free their slots, up to twenty per recipient
+        let info = mock_info("grantor", &coins(19, "usei"));
+        execute(deps.as_mut(), env.clone(), info, split(19)).unwrap();
+        let info = mock_info("grantor", &coins(1, "usei"));
+        let res = execute(deps.as_mut(), env, info, split(1));
+        match res.unwrap_err() {
+            ContractError::TooManyVestingGrants { recipient } => assert_eq!(recipient, "alice"),
+            e => panic!("unexpected error: {:?}", e),
+        }
     }
 }
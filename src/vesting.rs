@@ -0,0 +1,43 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Timestamp, Uint128};
+
+use crate::ContractError;
+
+/// Linear release of a share between `start` and `end`. Nothing is released before `cliff`
+/// (when set), at which point everything vested so far becomes available at once - a cliff
+/// equal to `end` releases the whole share in one go.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Vesting {
+    pub start: Timestamp,
+    pub end: Timestamp,
+    pub cliff: Option<Timestamp>,
+}
+
+impl Vesting {
+    pub fn validate(&self) -> Result<(), ContractError> {
+        if self.start > self.end {
+            return Err(ContractError::InvalidParams {});
+        }
+        if let Some(cliff) = self.cliff {
+            if cliff < self.start || cliff > self.end {
+                return Err(ContractError::InvalidParams {});
+            }
+        }
+        Ok(())
+    }
+
+    /// Portion of `total` vested at `now`, rounded down
+    pub fn vested(&self, total: Uint128, now: Timestamp) -> Uint128 {
+        if self.cliff.map_or(false, |cliff| now < cliff) || now < self.start {
+            return Uint128::zero();
+        }
+        if now >= self.end {
+            return total;
+        }
+        let elapsed = now.nanos() - self.start.nanos();
+        let duration = self.end.nanos() - self.start.nanos();
+        total.multiply_ratio(elapsed, duration)
+    }
+}